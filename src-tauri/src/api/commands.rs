@@ -1,7 +1,17 @@
-use tauri::{AppHandle, Emitter, State};
+use std::sync::Arc;
 
-use crate::api::events::{data_stream_event, metrics_event};
-use crate::core::types::{DataPacket, ParserDescriptor, PipelineItem, SourceInfo};
+use tauri::State;
+
+use crate::core::channel::ChannelConfig;
+use crate::core::encoding::EncodingConfig;
+use crate::core::hex_dump::HexDumpConfig;
+use crate::core::payload::PayloadEncoding;
+use crate::core::types::{
+    DataPacket, ParserDescriptor, SerialPortInfo, SourceInfo, SourceKind, TransmitJobInfo,
+};
+use crate::pipeline::PipelineSpec;
+use crate::services::scheduler::TransmitJobSpec;
+use crate::services::transaction::{TransactionRequest, TransactionResult};
 use crate::services::AppState;
 
 #[tauri::command]
@@ -16,6 +26,42 @@ pub fn list_parsers(state: State<AppState>) -> Result<Vec<ParserDescriptor>, Str
     Ok(manager.list_parsers())
 }
 
+#[tauri::command]
+pub fn list_serial_ports() -> Result<Vec<SerialPortInfo>, String> {
+    crate::sources::serial::available_ports()
+}
+
+#[tauri::command]
+pub fn create_source(
+    state: State<AppState>,
+    kind: SourceKind,
+    id: String,
+    label: String,
+    config: Option<serde_json::Value>,
+) -> Result<SourceInfo, String> {
+    let mut manager = state.manager.lock().map_err(|_| "lock poisoned".to_string())?;
+    manager.create_source(kind, &id, &label, config.unwrap_or_default())
+}
+
+#[tauri::command]
+pub fn remove_source(state: State<AppState>, source_id: String) -> Result<(), String> {
+    // 全程持有 scheduler 锁，避免停任务与删除 source 之间又有任务启动；加锁顺序与 start_job 相同
+    let mut scheduler = state.scheduler.lock().map_err(|_| "lock poisoned".to_string())?;
+    let exists = state
+        .manager
+        .lock()
+        .map_err(|_| "lock poisoned".to_string())?
+        .source_info(&source_id)
+        .is_some();
+    if !exists {
+        return Err(format!("source not found: {source_id}"));
+    }
+    // 先停掉该 source 的定时发送任务，它们持有的发送句柄不会随 source 一起释放
+    scheduler.stop_source_jobs(&source_id);
+    let mut manager = state.manager.lock().map_err(|_| "lock poisoned".to_string())?;
+    manager.remove_source(&source_id)
+}
+
 #[tauri::command]
 pub fn start_source(state: State<AppState>, source_id: String) -> Result<(), String> {
     let mut manager = state.manager.lock().map_err(|_| "lock poisoned".to_string())?;
@@ -28,46 +74,120 @@ pub fn stop_source(state: State<AppState>, source_id: String) -> Result<(), Stri
     manager.stop_source(&source_id)
 }
 
+#[tauri::command]
+pub fn configure_source(
+    state: State<AppState>,
+    source_id: String,
+    config: serde_json::Value,
+) -> Result<(), String> {
+    let mut manager = state.manager.lock().map_err(|_| "lock poisoned".to_string())?;
+    manager.configure_source(&source_id, config)
+}
+
+#[tauri::command]
+pub fn set_backpressure(
+    state: State<AppState>,
+    source_id: String,
+    config: ChannelConfig,
+) -> Result<(), String> {
+    let mut manager = state.manager.lock().map_err(|_| "lock poisoned".to_string())?;
+    manager.set_backpressure(&source_id, config)
+}
+
+#[tauri::command]
+pub fn set_source_encoding(
+    state: State<AppState>,
+    source_id: String,
+    config: Option<EncodingConfig>,
+) -> Result<(), String> {
+    let mut manager = state.manager.lock().map_err(|_| "lock poisoned".to_string())?;
+    manager.set_encoding(&source_id, config)
+}
+
+#[tauri::command]
+pub fn set_hex_dump(
+    state: State<AppState>,
+    source_id: String,
+    config: Option<HexDumpConfig>,
+) -> Result<(), String> {
+    let mut manager = state.manager.lock().map_err(|_| "lock poisoned".to_string())?;
+    manager.set_hex_dump(&source_id, config)
+}
+
 #[tauri::command]
 pub fn attach_pipeline(
     state: State<AppState>,
     source_id: String,
-    pipeline_id: String,
+    spec: PipelineSpec,
 ) -> Result<(), String> {
     let mut manager = state.manager.lock().map_err(|_| "lock poisoned".to_string())?;
-    // TODO: Create pipeline from configuration and attach to source.
-    let pipeline = crate::pipeline::Pipeline::new(pipeline_id);
+    let pipeline = manager.build_pipeline(&spec)?;
     manager.attach_pipeline(&source_id, pipeline)
 }
 
 #[tauri::command]
-pub fn mock_rx(app: AppHandle, state: State<AppState>, source_id: String, text: String) -> Result<(), String> {
-    let mut manager = state.manager.lock().map_err(|_| "lock poisoned".to_string())?;
-    let packet = DataPacket::new(&source_id, text.as_bytes().to_vec(), Some(text));
+pub fn send_data(
+    state: State<AppState>,
+    source_id: String,
+    payload: String,
+    encoding: Option<PayloadEncoding>,
+) -> Result<(), String> {
+    // 写入可能要等设备，取到发送句柄后先释放锁
+    let transmitter = state
+        .manager
+        .lock()
+        .map_err(|_| "lock poisoned".to_string())?
+        .transmitter(&source_id)?;
+    transmitter.send(&payload, encoding.unwrap_or_default())
+}
 
-    // 先发送原始数据包，再走管线输出解析结果
-    app.emit(&data_stream_event(&source_id), &packet)
-        .map_err(|err| err.to_string())?;
-
-    let (pipeline_id, outputs) = manager.ingest_packet(&source_id, packet)?;
-    let metrics_event = metrics_event(&pipeline_id);
-
-    for item in outputs {
-        match item {
-            PipelineItem::Metric(metric) => {
-                app.emit(&metrics_event, &metric)
-                    .map_err(|err| err.to_string())?;
-            }
-            PipelineItem::Event(event) => {
-                app.emit(&data_stream_event(&source_id), &event)
-                    .map_err(|err| err.to_string())?;
-            }
-            PipelineItem::Packet(packet) => {
-                app.emit(&data_stream_event(&source_id), &packet)
-                    .map_err(|err| err.to_string())?;
-            }
-        }
-    }
+// 等待应答会阻塞，放到阻塞线程池执行，不占用异步运行时的工作线程
+#[tauri::command]
+pub async fn transact(
+    state: State<'_, AppState>,
+    request: TransactionRequest,
+) -> Result<TransactionResult, String> {
+    let matcher = request.expect.compile()?;
+    let timeout = request.timeout()?;
+    let manager = Arc::clone(&state.manager);
+    tauri::async_runtime::spawn_blocking(move || {
+        crate::services::transaction::transact(
+            &manager,
+            &request.source_id,
+            &request.message,
+            matcher,
+            timeout,
+        )
+    })
+    .await
+    .map_err(|err| err.to_string())?
+}
+
+#[tauri::command]
+pub fn list_transmit_jobs(state: State<AppState>) -> Result<Vec<TransmitJobInfo>, String> {
+    let scheduler = state.scheduler.lock().map_err(|_| "lock poisoned".to_string())?;
+    Ok(scheduler.list_jobs())
+}
+
+#[tauri::command]
+pub fn start_transmit_job(
+    state: State<AppState>,
+    spec: TransmitJobSpec,
+) -> Result<TransmitJobInfo, String> {
+    let mut scheduler = state.scheduler.lock().map_err(|_| "lock poisoned".to_string())?;
+    scheduler.start_job(spec)
+}
+
+#[tauri::command]
+pub fn stop_transmit_job(state: State<AppState>, job_id: String) -> Result<(), String> {
+    let mut scheduler = state.scheduler.lock().map_err(|_| "lock poisoned".to_string())?;
+    scheduler.stop_job(&job_id)
+}
 
-    Ok(())
+#[tauri::command]
+pub fn mock_rx(state: State<AppState>, source_id: String, text: String) -> Result<(), String> {
+    let mut manager = state.manager.lock().map_err(|_| "lock poisoned".to_string())?;
+    let packet = DataPacket::new(&source_id, text.as_bytes().to_vec(), Some(text));
+    // 与真实数据相同：交给分发线程推送原始包并跑管线
+    manager.ingest_packet(&source_id, packet)
 }
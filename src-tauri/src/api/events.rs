@@ -1,5 +1,8 @@
 pub const DATA_STREAM_PREFIX: &str = "data_stream";
 pub const METRICS_PREFIX: &str = "metrics";
+pub const HEX_DUMP_PREFIX: &str = "hex_dump";
+pub const SERIAL_PORTS_CHANGED: &str = "serial_ports::changed";
+pub const TRANSMIT_JOB_CHANGED: &str = "transmit_job::changed";
 
 pub fn data_stream_event(source_id: &str) -> String {
     format!("{DATA_STREAM_PREFIX}::{source_id}")
@@ -8,3 +11,7 @@ pub fn data_stream_event(source_id: &str) -> String {
 pub fn metrics_event(pipeline_id: &str) -> String {
     format!("{METRICS_PREFIX}::{pipeline_id}")
 }
+
+pub fn hex_dump_event(source_id: &str) -> String {
+    format!("{HEX_DUMP_PREFIX}::{source_id}")
+}
@@ -0,0 +1,352 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use crate::core::types::DataPacket;
+
+pub const DEFAULT_CAPACITY: usize = 1024;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BackpressurePolicy {
+    // 队列满时阻塞 source 的读线程，不丢数据
+    #[default]
+    Block,
+    DropOldest,
+    DropNewest,
+    // 队列满时把新包拼接到队尾的包上，tags 或 text 有无不一致时退化为 DropOldest
+    Coalesce,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ChannelConfig {
+    pub capacity: usize,
+    pub policy: BackpressurePolicy,
+}
+
+impl Default for ChannelConfig {
+    fn default() -> Self {
+        Self {
+            capacity: DEFAULT_CAPACITY,
+            policy: BackpressurePolicy::default(),
+        }
+    }
+}
+
+impl ChannelConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.capacity == 0 {
+            return Err("capacity must be greater than 0".to_string());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChannelStats {
+    pub queued: usize,
+    pub dropped: u64,
+    pub coalesced: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Disconnected;
+
+struct State {
+    items: VecDeque<DataPacket>,
+    config: ChannelConfig,
+    senders: usize,
+    receiver_alive: bool,
+    dropped: u64,
+    coalesced: u64,
+}
+
+struct Shared {
+    state: Mutex<State>,
+    not_empty: Condvar,
+    not_full: Condvar,
+}
+
+impl Shared {
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|err| err.into_inner())
+    }
+}
+
+pub fn bounded(config: ChannelConfig) -> (PacketSender, PacketReceiver) {
+    let shared = Arc::new(Shared {
+        state: Mutex::new(State {
+            items: VecDeque::new(),
+            config,
+            senders: 1,
+            receiver_alive: true,
+            dropped: 0,
+            coalesced: 0,
+        }),
+        not_empty: Condvar::new(),
+        not_full: Condvar::new(),
+    });
+    (
+        PacketSender {
+            shared: Arc::clone(&shared),
+        },
+        PacketReceiver { shared },
+    )
+}
+
+pub struct PacketSender {
+    shared: Arc<Shared>,
+}
+
+impl PacketSender {
+    pub fn send(&self, packet: DataPacket) -> Result<(), Disconnected> {
+        let mut state = self.shared.lock();
+        loop {
+            if !state.receiver_alive {
+                return Err(Disconnected);
+            }
+            if state.items.len() < state.config.capacity {
+                break;
+            }
+            match state.config.policy {
+                BackpressurePolicy::Block => {
+                    state = self
+                        .shared
+                        .not_full
+                        .wait(state)
+                        .unwrap_or_else(|err| err.into_inner());
+                }
+                BackpressurePolicy::DropNewest => {
+                    state.dropped += 1;
+                    return Ok(());
+                }
+                BackpressurePolicy::DropOldest => {
+                    state.items.pop_front();
+                    state.dropped += 1;
+                    break;
+                }
+                BackpressurePolicy::Coalesce => {
+                    let merged = match state.items.back_mut() {
+                        Some(last)
+                            if last.tags == packet.tags
+                                && last.text.is_some() == packet.text.is_some() =>
+                        {
+                            coalesce(last, &packet);
+                            true
+                        }
+                        _ => false,
+                    };
+                    if merged {
+                        state.coalesced += 1;
+                        return Ok(());
+                    }
+                    state.items.pop_front();
+                    state.dropped += 1;
+                    break;
+                }
+            }
+        }
+        state.items.push_back(packet);
+        drop(state);
+        self.shared.not_empty.notify_one();
+        Ok(())
+    }
+
+    pub fn stats(&self) -> ChannelStats {
+        let state = self.shared.lock();
+        ChannelStats {
+            queued: state.items.len(),
+            dropped: state.dropped,
+            coalesced: state.coalesced,
+        }
+    }
+
+    pub fn config(&self) -> ChannelConfig {
+        self.shared.lock().config
+    }
+
+    pub fn set_config(&self, config: ChannelConfig) {
+        let mut state = self.shared.lock();
+        state.config = config;
+        // 缩容时按新策略处理超出的部分；Block 策略只等待消费方追上
+        if config.policy != BackpressurePolicy::Block {
+            while state.items.len() > config.capacity {
+                state.items.pop_front();
+                state.dropped += 1;
+            }
+        }
+        drop(state);
+        self.shared.not_full.notify_all();
+    }
+}
+
+impl Clone for PacketSender {
+    fn clone(&self) -> Self {
+        self.shared.lock().senders += 1;
+        Self {
+            shared: Arc::clone(&self.shared),
+        }
+    }
+}
+
+impl Drop for PacketSender {
+    fn drop(&mut self) {
+        let mut state = self.shared.lock();
+        state.senders -= 1;
+        if state.senders == 0 {
+            drop(state);
+            self.shared.not_empty.notify_all();
+        }
+    }
+}
+
+pub struct PacketReceiver {
+    shared: Arc<Shared>,
+}
+
+impl PacketReceiver {
+    // 超时返回 Ok(None)，所有 Sender 都已丢弃且队列为空时返回 Err
+    pub fn recv_timeout(&self, timeout: Duration) -> Result<Option<DataPacket>, Disconnected> {
+        let deadline = Instant::now() + timeout;
+        let mut state = self.shared.lock();
+        loop {
+            if let Some(packet) = state.items.pop_front() {
+                drop(state);
+                self.shared.not_full.notify_one();
+                return Ok(Some(packet));
+            }
+            if state.senders == 0 {
+                return Err(Disconnected);
+            }
+            let now = Instant::now();
+            if now >= deadline {
+                return Ok(None);
+            }
+            state = self
+                .shared
+                .not_empty
+                .wait_timeout(state, deadline - now)
+                .map(|(state, _)| state)
+                .unwrap_or_else(|err| err.into_inner().0);
+        }
+    }
+}
+
+impl Drop for PacketReceiver {
+    fn drop(&mut self) {
+        let mut state = self.shared.lock();
+        state.receiver_alive = false;
+        state.items.clear();
+        drop(state);
+        self.shared.not_full.notify_all();
+    }
+}
+
+// 调用方保证两个包都有或都没有 text，拼接后 raw 与 text 仍然对应
+fn coalesce(into: &mut DataPacket, next: &DataPacket) {
+    into.raw.extend_from_slice(&next.raw);
+    if let (Some(text), Some(more)) = (&mut into.text, &next.text) {
+        text.push_str(more);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WAIT: Duration = Duration::from_millis(50);
+
+    fn channel(capacity: usize, policy: BackpressurePolicy) -> (PacketSender, PacketReceiver) {
+        bounded(ChannelConfig { capacity, policy })
+    }
+
+    fn text(text: &str) -> DataPacket {
+        DataPacket::new("s", text.as_bytes().to_vec(), Some(text.to_string()))
+    }
+
+    fn drain(receiver: &PacketReceiver) -> Vec<Vec<u8>> {
+        let mut raws = Vec::new();
+        while let Ok(Some(packet)) = receiver.recv_timeout(Duration::ZERO) {
+            raws.push(packet.raw);
+        }
+        raws
+    }
+
+    #[test]
+    fn drop_policies_keep_the_expected_end() {
+        let (sender, receiver) = channel(2, BackpressurePolicy::DropNewest);
+        for payload in ["1", "2", "3"] {
+            sender.send(text(payload)).unwrap();
+        }
+        assert_eq!(sender.stats().dropped, 1);
+        assert_eq!(drain(&receiver), vec![b"1", b"2"]);
+
+        let (sender, receiver) = channel(2, BackpressurePolicy::DropOldest);
+        for payload in ["1", "2", "3"] {
+            sender.send(text(payload)).unwrap();
+        }
+        assert_eq!(
+            sender.stats(),
+            ChannelStats {
+                queued: 2,
+                dropped: 1,
+                coalesced: 0,
+            }
+        );
+        assert_eq!(drain(&receiver), vec![b"2", b"3"]);
+    }
+
+    #[test]
+    fn block_waits_until_receiver_makes_room() {
+        let (sender, receiver) = channel(1, BackpressurePolicy::Block);
+        sender.send(text("1")).unwrap();
+        let blocked = std::thread::spawn(move || {
+            sender.send(text("2")).unwrap();
+            sender
+        });
+        std::thread::sleep(WAIT);
+        assert!(!blocked.is_finished());
+
+        assert_eq!(receiver.recv_timeout(WAIT).unwrap().unwrap().raw, b"1");
+        let sender = blocked.join().unwrap();
+        assert_eq!(receiver.recv_timeout(WAIT).unwrap().unwrap().raw, b"2");
+        assert_eq!(sender.stats().dropped, 0);
+
+        // 接收端丢弃后阻塞的发送方要被唤醒并返回错误
+        sender.send(text("3")).unwrap();
+        let blocked = std::thread::spawn(move || sender.send(text("4")));
+        std::thread::sleep(WAIT);
+        drop(receiver);
+        assert_eq!(blocked.join().unwrap(), Err(Disconnected));
+    }
+
+    #[test]
+    fn coalesce_merges_packets_of_the_same_shape() {
+        let (sender, receiver) = channel(1, BackpressurePolicy::Coalesce);
+        sender.send(text("ab")).unwrap();
+        sender.send(text("cd")).unwrap();
+        assert_eq!(sender.stats().coalesced, 1);
+        let packet = receiver.recv_timeout(WAIT).unwrap().unwrap();
+        assert_eq!(packet.raw, b"abcd");
+        assert_eq!(packet.text.as_deref(), Some("abcd"));
+
+        // text 有无不同的包不拼接，按 DropOldest 处理
+        sender.send(text("ab")).unwrap();
+        sender.send(DataPacket::new("s", vec![0xFF], None)).unwrap();
+        let mut tagged = text("ef");
+        tagged.tags.push("peer:a".to_string());
+        sender.send(tagged).unwrap();
+        assert_eq!(
+            sender.stats(),
+            ChannelStats {
+                queued: 1,
+                dropped: 2,
+                coalesced: 1,
+            }
+        );
+        assert_eq!(drain(&receiver), vec![b"ef"]);
+    }
+}
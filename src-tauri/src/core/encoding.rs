@@ -0,0 +1,307 @@
+// 字节流到文本的解码：流式处理跨包截断的多字节字符，非法字节按策略替换或显示为 \xNN，不会丢包
+use encoding_rs::{CoderResult, Decoder, DecoderResult, Encoding, GBK, UTF_8};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TextEncoding {
+    #[default]
+    Utf8,
+    Latin1,
+    // 按 GB18030 解码，兼容 GBK 与 GB2312
+    Gbk,
+    // 合法的 UTF-8 前缀按 UTF-8 输出，从第一个非法序列开始按 GBK 解码
+    Auto,
+}
+
+impl TextEncoding {
+    // 发送文本时按同一编码转回字节；无法表示的字符直接报错，不静默替换
+    pub fn encode(self, text: &str) -> Result<Vec<u8>, String> {
+        match self {
+            // auto 发送时按 UTF-8
+            TextEncoding::Utf8 | TextEncoding::Auto => Ok(text.as_bytes().to_vec()),
+            TextEncoding::Latin1 => text
+                .chars()
+                .map(|c| {
+                    u8::try_from(c).map_err(|_| format!("{c:?} is not representable in latin1"))
+                })
+                .collect(),
+            TextEncoding::Gbk => {
+                let (bytes, _, had_errors) = GBK.encode(text);
+                if had_errors {
+                    return Err("text contains characters not representable in gbk".to_string());
+                }
+                Ok(bytes.into_owned())
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InvalidBytes {
+    // 替换为 U+FFFD
+    #[default]
+    Lossy,
+    // 以 \xNN 形式保留原始字节
+    Hex,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EncodingConfig {
+    pub encoding: TextEncoding,
+    pub invalid: InvalidBytes,
+}
+
+pub struct TextDecoder {
+    config: EncodingConfig,
+    // 末尾不完整的多字节字符，与下一次输入拼接；数据流停下时由 flush 输出
+    pending: Vec<u8>,
+}
+
+impl TextDecoder {
+    pub fn new(config: EncodingConfig) -> Self {
+        Self {
+            config,
+            pending: Vec::new(),
+        }
+    }
+
+    pub fn config(&self) -> EncodingConfig {
+        self.config
+    }
+
+    pub fn decode(&mut self, bytes: &[u8]) -> String {
+        let mut buf = std::mem::take(&mut self.pending);
+        buf.extend_from_slice(bytes);
+        match self.config.encoding {
+            TextEncoding::Latin1 => buf.iter().map(|byte| char::from(*byte)).collect(),
+            TextEncoding::Utf8 => {
+                self.pending = buf.split_off(utf8_boundary(&buf));
+                decode_complete(UTF_8, self.config.invalid, &buf)
+            }
+            TextEncoding::Gbk => {
+                self.pending = buf.split_off(gbk_boundary(&buf));
+                decode_complete(GBK, self.config.invalid, &buf)
+            }
+            TextEncoding::Auto => self.decode_auto(buf),
+        }
+    }
+
+    // 输出等不到后续字节的残留字符（按非法字节处理）；没有残留时返回 None
+    pub fn flush(&mut self) -> Option<String> {
+        if self.pending.is_empty() {
+            return None;
+        }
+        let pending = std::mem::take(&mut self.pending);
+        let encoding = match self.config.encoding {
+            TextEncoding::Gbk => GBK,
+            _ => UTF_8,
+        };
+        Some(decode_complete(encoding, self.config.invalid, &pending))
+    }
+
+    // 合法的 UTF-8 前缀照常输出，其余部分按 GBK；末尾不完整的 UTF-8 字符先留到下一次。
+    // 较短的 GBK 片段偶尔也是合法 UTF-8，此时按 UTF-8 输出
+    fn decode_auto(&mut self, mut buf: Vec<u8>) -> String {
+        let valid = match std::str::from_utf8(&buf) {
+            Ok(_) => buf.len(),
+            Err(err) => err.valid_up_to(),
+        };
+        let mut rest = buf.split_off(valid);
+        let mut out = String::from_utf8(buf).unwrap_or_default();
+        if utf8_boundary(&rest) == 0 {
+            self.pending = rest;
+            return out;
+        }
+        self.pending = rest.split_off(gbk_boundary(&rest));
+        out.push_str(&decode_complete(GBK, self.config.invalid, &rest));
+        out
+    }
+}
+
+// 输入不含被截断的字符，每次用新的解码器一次解完
+fn decode_complete(encoding: &'static Encoding, invalid: InvalidBytes, bytes: &[u8]) -> String {
+    let mut decoder = encoding.new_decoder_without_bom_handling();
+    decode_with(&mut decoder, invalid, bytes)
+}
+
+fn decode_with(decoder: &mut Decoder, invalid: InvalidBytes, bytes: &[u8]) -> String {
+    let mut out = String::new();
+    match invalid {
+        InvalidBytes::Lossy => decode_lossy(decoder, bytes, &mut out),
+        InvalidBytes::Hex => decode_hex(decoder, bytes, &mut out),
+    }
+    out
+}
+
+// 末尾不完整的 UTF-8 字符的起始位置：首字节声明的长度超过剩余字节数，且其后都是延续字节
+fn utf8_boundary(bytes: &[u8]) -> usize {
+    for back in 1..=bytes.len().min(3) {
+        let index = bytes.len() - back;
+        let width = match bytes[index] {
+            0x80..=0xBF => continue,
+            0xC2..=0xDF => 2,
+            0xE0..=0xEF => 3,
+            0xF0..=0xF4 => 4,
+            _ => 1,
+        };
+        return if width > back { index } else { bytes.len() };
+    }
+    bytes.len()
+}
+
+// 末尾不完整的 GB18030 字符的起始位置：0x81-0xFE 为首字节，第二字节为 0x30-0x39 时是四字节字符，否则为双字节
+fn gbk_boundary(bytes: &[u8]) -> usize {
+    let mut i = 0;
+    while i < bytes.len() {
+        let width = match bytes[i] {
+            0x81..=0xFE => match bytes.get(i + 1) {
+                Some(0x30..=0x39) => 4,
+                _ => 2,
+            },
+            _ => 1,
+        };
+        if i + width > bytes.len() {
+            return i;
+        }
+        i += width;
+    }
+    bytes.len()
+}
+
+fn decode_lossy(decoder: &mut Decoder, mut bytes: &[u8], out: &mut String) {
+    loop {
+        out.reserve(
+            decoder
+                .max_utf8_buffer_length(bytes.len())
+                .unwrap_or(bytes.len() * 3 + 16),
+        );
+        let (result, read, _) = decoder.decode_to_string(bytes, out, true);
+        bytes = &bytes[read..];
+        if result == CoderResult::InputEmpty {
+            return;
+        }
+    }
+}
+
+fn decode_hex(decoder: &mut Decoder, mut bytes: &[u8], out: &mut String) {
+    loop {
+        out.reserve(
+            decoder
+                .max_utf8_buffer_length_without_replacement(bytes.len())
+                .unwrap_or(bytes.len() * 3 + 16),
+        );
+        let (result, read) = decoder.decode_to_string_without_replacement(bytes, out, true);
+        match result {
+            DecoderResult::InputEmpty => return,
+            DecoderResult::OutputFull => {}
+            DecoderResult::Malformed(bad, extra) => {
+                // 非法序列位于本次已读部分的末尾之前 extra 字节处
+                let end = read - extra as usize;
+                let start = end.saturating_sub(bad as usize);
+                for byte in &bytes[start..end] {
+                    out.push_str(&format!("\\x{byte:02X}"));
+                }
+            }
+        }
+        bytes = &bytes[read..];
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // "温度" 的 GBK 编码
+    const GBK_TEMP: [u8; 4] = [0xCE, 0xC2, 0xB6, 0xC8];
+
+    fn decoder(encoding: TextEncoding) -> TextDecoder {
+        TextDecoder::new(EncodingConfig {
+            encoding,
+            invalid: InvalidBytes::Lossy,
+        })
+    }
+
+    fn decode_split(decoder: &mut TextDecoder, bytes: &[u8], cut: usize) -> String {
+        let mut text = decoder.decode(&bytes[..cut]);
+        text.push_str(&decoder.decode(&bytes[cut..]));
+        text
+    }
+
+    #[test]
+    fn utf8_split_across_reads() {
+        let bytes = "温度=23℃".as_bytes();
+        for encoding in [TextEncoding::Utf8, TextEncoding::Auto] {
+            for cut in 0..=bytes.len() {
+                assert_eq!(
+                    decode_split(&mut decoder(encoding), bytes, cut),
+                    "温度=23℃",
+                    "{encoding:?} cut at {cut}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn gbk_decodes_chinese() {
+        let mut bytes = GBK_TEMP.to_vec();
+        bytes.extend_from_slice(b"=23");
+        assert_eq!(decoder(TextEncoding::Gbk).decode(&bytes), "温度=23");
+        assert_eq!(TextEncoding::Gbk.encode("温度=23").unwrap(), bytes);
+    }
+
+    #[test]
+    fn auto_falls_back_to_gbk() {
+        let mut bytes = b"t:".to_vec();
+        bytes.extend_from_slice(&GBK_TEMP);
+        bytes.push(b'\n');
+        for cut in 0..=bytes.len() {
+            assert_eq!(
+                decode_split(&mut decoder(TextEncoding::Auto), &bytes, cut),
+                "t:温度\n",
+                "cut at {cut}"
+            );
+        }
+    }
+
+    #[test]
+    fn hex_mode_shows_invalid_bytes() {
+        let mut decoder = TextDecoder::new(EncodingConfig {
+            encoding: TextEncoding::Utf8,
+            invalid: InvalidBytes::Hex,
+        });
+        assert_eq!(decoder.decode(b"a\xFFb"), "a\\xFFb");
+    }
+
+    #[test]
+    fn auto_keeps_valid_utf8_prefix() {
+        let mut bytes = "温度=23 ".as_bytes().to_vec();
+        bytes.push(0xFF);
+        for cut in 0..=bytes.len() {
+            assert_eq!(
+                decode_split(&mut decoder(TextEncoding::Auto), &bytes, cut),
+                "温度=23 \u{FFFD}",
+                "cut at {cut}"
+            );
+        }
+    }
+
+    #[test]
+    fn flush_releases_truncated_char() {
+        let bytes = "温".as_bytes();
+        let mut lossy = decoder(TextEncoding::Auto);
+        assert_eq!(lossy.decode(&bytes[..2]), "");
+        assert_eq!(lossy.flush().as_deref(), Some("\u{FFFD}"));
+        assert_eq!(lossy.flush(), None);
+        assert_eq!(lossy.decode(b"ok"), "ok");
+
+        let mut hex = TextDecoder::new(EncodingConfig {
+            encoding: TextEncoding::Utf8,
+            invalid: InvalidBytes::Hex,
+        });
+        assert_eq!(hex.decode(&bytes[..2]), "");
+        assert_eq!(hex.flush().as_deref(), Some("\\xE6\\xB8"));
+    }
+}
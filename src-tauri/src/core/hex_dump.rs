@@ -0,0 +1,150 @@
+// 把原始字节格式化为 hex dump 行（偏移、十六进制、ASCII），避免前端逐字节处理大量二进制数据
+use serde::{Deserialize, Serialize};
+
+use crate::core::types::Timestamp;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HexDumpConfig {
+    // 每行字节数
+    pub width: usize,
+    // 每多少字节多插一个空格分组，0 表示不分组
+    pub group: usize,
+    pub uppercase: bool,
+}
+
+impl Default for HexDumpConfig {
+    fn default() -> Self {
+        Self {
+            width: 16,
+            group: 8,
+            uppercase: true,
+        }
+    }
+}
+
+impl HexDumpConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.width == 0 || self.width > 64 {
+            return Err("width must be between 1 and 64".to_string());
+        }
+        if self.group > self.width {
+            return Err("group must not exceed width".to_string());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HexDumpRow {
+    // 该行首字节在 source 字节流中的偏移
+    pub offset: u64,
+    // 不足一行时用空格补齐，保证等宽字体下 ASCII 列对齐
+    pub hex: String,
+    // 不可打印字节显示为 '.'
+    pub ascii: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HexDump {
+    pub ts: Timestamp,
+    pub source_id: String,
+    pub tags: Vec<String>,
+    pub rows: Vec<HexDumpRow>,
+}
+
+pub fn hex_dump_rows(bytes: &[u8], base_offset: u64, config: &HexDumpConfig) -> Vec<HexDumpRow> {
+    bytes
+        .chunks(config.width)
+        .enumerate()
+        .map(|(index, chunk)| {
+            let mut hex = String::with_capacity(config.width * 3 + config.width);
+            for column in 0..config.width {
+                if column > 0 {
+                    hex.push(' ');
+                    if config.group > 0 && column % config.group == 0 {
+                        hex.push(' ');
+                    }
+                }
+                match chunk.get(column) {
+                    Some(byte) if config.uppercase => hex.push_str(&format!("{byte:02X}")),
+                    Some(byte) => hex.push_str(&format!("{byte:02x}")),
+                    None => hex.push_str("  "),
+                }
+            }
+            let ascii = chunk
+                .iter()
+                .map(|byte| {
+                    if byte.is_ascii_graphic() || *byte == b' ' {
+                        char::from(*byte)
+                    } else {
+                        '.'
+                    }
+                })
+                .collect();
+            HexDumpRow {
+                offset: base_offset + (index * config.width) as u64,
+                hex,
+                ascii,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(width: usize, group: usize, uppercase: bool) -> HexDumpConfig {
+        HexDumpConfig {
+            width,
+            group,
+            uppercase,
+        }
+    }
+
+    #[test]
+    fn rows_follow_width_and_base_offset() {
+        let bytes: Vec<u8> = (0..8).collect();
+        let rows = hex_dump_rows(&bytes, 0x100, &config(4, 0, true));
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].offset, 0x100);
+        assert_eq!(rows[0].hex, "00 01 02 03");
+        assert_eq!(rows[1].offset, 0x104);
+        assert_eq!(rows[1].hex, "04 05 06 07");
+    }
+
+    #[test]
+    fn groups_add_extra_space_and_case_follows_config() {
+        let bytes = [0xAB, 0xCD, 0xEF, 0x01, 0x23, 0x45];
+        let rows = hex_dump_rows(&bytes, 0, &config(6, 2, false));
+        assert_eq!(rows[0].hex, "ab cd  ef 01  23 45");
+        let rows = hex_dump_rows(&bytes, 0, &config(6, 0, true));
+        assert_eq!(rows[0].hex, "AB CD EF 01 23 45");
+    }
+
+    #[test]
+    fn ascii_column_masks_unprintable_bytes() {
+        let rows = hex_dump_rows(b"A z\x00\x7F\xFF~", 0, &config(8, 0, true));
+        assert_eq!(rows[0].ascii, "A z...~");
+    }
+
+    #[test]
+    fn final_partial_row_pads_hex_only() {
+        let rows = hex_dump_rows(b"abcde", 16, &config(4, 2, true));
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[1].offset, 20);
+        assert_eq!(rows[1].hex, "65          ");
+        assert_eq!(rows[1].hex.len(), rows[0].hex.len());
+        assert_eq!(rows[1].ascii, "e");
+        assert!(hex_dump_rows(&[], 0, &HexDumpConfig::default()).is_empty());
+    }
+
+    #[test]
+    fn validate_rejects_bad_width_and_group() {
+        assert!(HexDumpConfig::default().validate().is_ok());
+        assert!(config(0, 0, true).validate().is_err());
+        assert!(config(65, 0, true).validate().is_err());
+        assert!(config(4, 8, true).validate().is_err());
+    }
+}
@@ -1,2 +1,6 @@
+pub mod channel;
+pub mod encoding;
+pub mod hex_dump;
+pub mod payload;
 pub mod traits;
 pub mod types;
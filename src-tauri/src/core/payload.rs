@@ -0,0 +1,181 @@
+// 发送数据的解析：把前端输入框里的文本、hex 字符串或 base64 转成字节，并按需追加行尾
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+
+use crate::core::encoding::TextEncoding;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PayloadFormat {
+    // 按 source 配置的文本编码转换，未配置时为 UTF-8
+    #[default]
+    Text,
+    // 例如 "AA 55 01"、"aa5501"、"0xAA,0x55"
+    Hex,
+    Base64,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LineEnding {
+    #[default]
+    None,
+    Lf,
+    Cr,
+    CrLf,
+}
+
+impl LineEnding {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            LineEnding::None => "",
+            LineEnding::Lf => "\n",
+            LineEnding::Cr => "\r",
+            LineEnding::CrLf => "\r\n",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PayloadEncoding {
+    pub format: PayloadFormat,
+    pub line_ending: LineEnding,
+}
+
+impl PayloadEncoding {
+    pub fn encode(&self, payload: &str, text_encoding: TextEncoding) -> Result<Vec<u8>, String> {
+        let mut bytes = match self.format {
+            PayloadFormat::Text => text_encoding.encode(payload)?,
+            PayloadFormat::Hex => parse_hex(payload)?,
+            PayloadFormat::Base64 => {
+                let compact: String = payload.split_whitespace().collect();
+                STANDARD
+                    .decode(compact)
+                    .map_err(|err| format!("invalid base64 payload: {err}"))?
+            }
+        };
+        bytes.extend_from_slice(self.line_ending.as_str().as_bytes());
+        Ok(bytes)
+    }
+}
+
+pub fn parse_hex(payload: &str) -> Result<Vec<u8>, String> {
+    let mut bytes = Vec::new();
+    for token in payload
+        .split(|c: char| c.is_whitespace() || matches!(c, ',' | ':' | '-'))
+        .filter(|token| !token.is_empty())
+    {
+        let digits = token
+            .strip_prefix("0x")
+            .or_else(|| token.strip_prefix("0X"))
+            .unwrap_or(token);
+        if !digits.bytes().all(|digit| digit.is_ascii_hexdigit()) {
+            return Err(format!("invalid hex payload: `{token}` is not hexadecimal"));
+        }
+        if digits.is_empty() {
+            return Err(format!("invalid hex payload: `{token}` has no digits"));
+        }
+        if digits.len() % 2 != 0 {
+            return Err(format!(
+                "invalid hex payload: `{token}` has an odd number of digits"
+            ));
+        }
+        for pair in digits.as_bytes().chunks(2) {
+            let high = (pair[0] as char).to_digit(16).unwrap_or_default();
+            let low = (pair[1] as char).to_digit(16).unwrap_or_default();
+            bytes.push((high * 16 + low) as u8);
+        }
+    }
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encoding(format: PayloadFormat, line_ending: LineEnding) -> PayloadEncoding {
+        PayloadEncoding {
+            format,
+            line_ending,
+        }
+    }
+
+    #[test]
+    fn hex_accepts_common_notations() {
+        let expected = vec![0xAA, 0x55, 0x01];
+        for payload in [
+            "AA 55 01",
+            "aa5501",
+            "0xAA,0x55,0x01",
+            "AA:55-01",
+            " 0XaA\t55\n01 ",
+        ] {
+            assert_eq!(parse_hex(payload).unwrap(), expected, "{payload:?}");
+        }
+        assert!(parse_hex("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn hex_errors_name_the_token() {
+        assert_eq!(
+            parse_hex("AA 0x").unwrap_err(),
+            "invalid hex payload: `0x` has no digits"
+        );
+        assert_eq!(
+            parse_hex("AA 5").unwrap_err(),
+            "invalid hex payload: `5` has an odd number of digits"
+        );
+        assert_eq!(
+            parse_hex("AA GG").unwrap_err(),
+            "invalid hex payload: `GG` is not hexadecimal"
+        );
+    }
+
+    #[test]
+    fn base64_ignores_whitespace() {
+        let payload = encoding(PayloadFormat::Base64, LineEnding::None);
+        assert_eq!(
+            payload.encode("qlUB\n", TextEncoding::Utf8).unwrap(),
+            vec![0xAA, 0x55, 0x01]
+        );
+        assert_eq!(
+            payload.encode("aGVs bG8=", TextEncoding::Utf8).unwrap(),
+            b"hello"
+        );
+        assert!(payload
+            .encode("not base64!", TextEncoding::Utf8)
+            .unwrap_err()
+            .starts_with("invalid base64 payload"));
+    }
+
+    #[test]
+    fn line_ending_is_appended_after_payload() {
+        let cases = [
+            (LineEnding::None, &b"AT"[..]),
+            (LineEnding::Lf, b"AT\n"),
+            (LineEnding::Cr, b"AT\r"),
+            (LineEnding::CrLf, b"AT\r\n"),
+        ];
+        for (line_ending, expected) in cases {
+            let payload = encoding(PayloadFormat::Text, line_ending);
+            assert_eq!(payload.encode("AT", TextEncoding::Utf8).unwrap(), expected);
+        }
+        let payload = encoding(PayloadFormat::Hex, LineEnding::CrLf);
+        assert_eq!(
+            payload.encode("41 54", TextEncoding::Utf8).unwrap(),
+            b"AT\r\n"
+        );
+    }
+
+    #[test]
+    fn text_uses_source_encoding() {
+        let payload = PayloadEncoding::default();
+        assert_eq!(
+            payload.encode("温", TextEncoding::Gbk).unwrap(),
+            vec![0xCE, 0xC2]
+        );
+        assert!(payload.encode("温", TextEncoding::Latin1).is_err());
+    }
+}
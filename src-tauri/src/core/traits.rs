@@ -1,6 +1,14 @@
-use std::sync::mpsc::Sender;
+use std::sync::Arc;
 
-use crate::core::types::{DataPacket, SourceKind, SourceStatus};
+use crate::core::channel::PacketSender;
+use crate::core::types::{SourceKind, SourceStatus};
+
+// 写句柄可以拿到 StreamManager 的锁之外使用，写入慢的设备不会挡住其它命令
+pub trait SourceWriter: Send + Sync {
+    fn write(&self, data: &[u8]) -> Result<(), String>;
+}
+
+pub type SharedWriter = Arc<dyn SourceWriter>;
 
 #[allow(dead_code)]
 pub trait DataSource: Send {
@@ -11,6 +19,22 @@ pub trait DataSource: Send {
     fn start(&mut self) -> Result<(), String>;
     fn stop(&mut self) -> Result<(), String>;
 
-    // TODO: Replace with async channel and backpressure handling.
-    fn set_sender(&mut self, sender: Sender<DataPacket>);
+    fn set_sender(&mut self, sender: PacketSender);
+
+    // 热插拔设备被拔出时调用，返回该 source 是否正在使用此设备
+    fn device_removed(&mut self, _device: &str) -> bool {
+        false
+    }
+
+    // 向设备发送数据的句柄，source 重启后仍然有效；只读的 source 保持默认实现
+    fn writer(&self) -> Result<SharedWriter, String> {
+        Err(format!("source does not support writing: {}", self.id()))
+    }
+
+    fn configure(&mut self, _config: serde_json::Value) -> Result<(), String> {
+        Err(format!(
+            "source does not accept configuration: {}",
+            self.id()
+        ))
+    }
 }
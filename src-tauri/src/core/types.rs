@@ -1,40 +1,134 @@
 use serde::{Deserialize, Serialize};
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::sync::OnceLock;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use crate::core::channel::ChannelConfig;
+use crate::core::encoding::EncodingConfig;
+use crate::core::hex_dump::HexDumpConfig;
+
+// mono_micros 为进程内单调时钟（微秒），用于计算间隔；unix_micros 由启动时记录的墙钟锚点加上单调时长得出，
+// 不受系统时间回拨影响
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct Timestamp {
+    pub mono_micros: u64,
+    pub unix_micros: u64,
+}
+
+struct ClockAnchor {
+    instant: Instant,
+    unix_micros: u64,
+}
+
+static CLOCK_ANCHOR: OnceLock<ClockAnchor> = OnceLock::new();
+
+impl Timestamp {
+    pub fn now() -> Self {
+        let anchor = CLOCK_ANCHOR.get_or_init(|| ClockAnchor {
+            instant: Instant::now(),
+            unix_micros: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_micros() as u64,
+        });
+        let mono_micros = anchor.instant.elapsed().as_micros() as u64;
+        Self {
+            mono_micros,
+            unix_micros: anchor.unix_micros + mono_micros,
+        }
+    }
+
+    pub fn unix_millis(&self) -> u64 {
+        self.unix_micros / 1000
+    }
+}
+
+// 发送出去的数据以带此 tag 的 DataPacket 回显到 data_stream，不进入解析管线
+pub const TX_TAG: &str = "tx";
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DataPacket {
-    pub ts_millis: u128,
+    pub ts: Timestamp,
     pub source_id: String,
     pub raw: Vec<u8>,
     pub text: Option<String>,
     pub tags: Vec<String>,
+    // text 上的样式区间，由 ansi stage 从 SGR 转义序列生成
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub spans: Vec<TextSpan>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TextStyle {
+    // 0-15 号色用名字（red、bright_red），其余为 #rrggbb
+    pub fg: Option<String>,
+    pub bg: Option<String>,
+    pub bold: bool,
+    pub dim: bool,
+    pub italic: bool,
+    pub underline: bool,
+    pub inverse: bool,
+}
+
+// start/end 为 text 中的字符（char）下标，左闭右开
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TextSpan {
+    pub start: usize,
+    pub end: usize,
+    pub style: TextStyle,
 }
 
 impl DataPacket {
     pub fn new(source_id: impl Into<String>, raw: Vec<u8>, text: Option<String>) -> Self {
         Self {
-            ts_millis: now_millis(),
+            ts: Timestamp::now(),
             source_id: source_id.into(),
             raw,
             text,
             tags: Vec::new(),
+            spans: Vec::new(),
         }
     }
+
+    // 读线程在拿到字节的那一刻取时间戳，避免把缓冲/分配的耗时算进去
+    pub fn with_timestamp(mut self, ts: Timestamp) -> Self {
+        self.ts = ts;
+        self
+    }
+
+    pub fn is_tx(&self) -> bool {
+        self.tags.iter().any(|tag| tag == TX_TAG)
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ParsedEvent {
-    pub ts_millis: u128,
+    pub ts: Timestamp,
     pub kind: String,
     pub payload: serde_json::Value,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Metric {
-    pub ts_millis: u128,
+    pub ts: Timestamp,
     pub source_id: String,
     pub name: String,
     pub value: f64,
+    // 设备自带的时间戳（单位由设备决定），例如 CSV 中指定的时间列
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub device_ts: Option<f64>,
+}
+
+impl Metric {
+    // 指标继承来源数据包的时间戳与 source_id
+    pub fn from_packet(packet: &DataPacket, name: impl Into<String>, value: f64) -> Self {
+        Self {
+            ts: packet.ts,
+            source_id: packet.source_id.clone(),
+            name: name.into(),
+            value,
+            device_ts: None,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -65,6 +159,105 @@ pub struct SourceInfo {
     pub label: String,
     pub kind: SourceKind,
     pub status: SourceStatus,
+    pub backpressure: ChannelConfig,
+    // None 表示不在 source 层解码，text 保持为空
+    pub encoding: Option<EncodingConfig>,
+    // 非空时按该配置在 hex_dump::<id> 事件上推送 hex dump 行
+    pub hex_dump: Option<HexDumpConfig>,
+    pub queued_packets: usize,
+    pub dropped_packets: u64,
+    pub coalesced_packets: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SerialPortInfo {
+    pub name: String,
+    pub port_type: String,
+    pub vid: Option<u16>,
+    pub pid: Option<u16>,
+    pub serial_number: Option<String>,
+    pub manufacturer: Option<String>,
+    pub product: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TransmitJobState {
+    Running,
+    Completed,
+    Stopped,
+    Failed(String),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransmitJobInfo {
+    pub id: String,
+    pub source_id: String,
+    pub mode: String,
+    pub state: TransmitJobState,
+    pub sent: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SerialPortsChanged {
+    pub added: Vec<SerialPortInfo>,
+    pub removed: Vec<String>,
+    pub ports: Vec<SerialPortInfo>,
+    // 因设备被拔出而被置为 Error("disconnected") 的 source id
+    pub disconnected_sources: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ParamType {
+    Bool,
+    Integer,
+    Number,
+    String,
+    Enum,
+    StringList,
+    // 结构由 stage 自己校验，前端按 JSON 编辑
+    Json,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ParamSchema {
+    pub name: String,
+    pub param_type: ParamType,
+    pub default: serde_json::Value,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+    pub options: Vec<String>,
+    pub description: String,
+}
+
+impl ParamSchema {
+    pub fn new(
+        name: &str,
+        param_type: ParamType,
+        default: serde_json::Value,
+        description: &str,
+    ) -> Self {
+        Self {
+            name: name.to_string(),
+            param_type,
+            default,
+            min: None,
+            max: None,
+            options: Vec::new(),
+            description: description.to_string(),
+        }
+    }
+
+    pub fn range(mut self, min: f64, max: f64) -> Self {
+        self.min = Some(min);
+        self.max = Some(max);
+        self
+    }
+
+    pub fn options(mut self, options: &[&str]) -> Self {
+        self.options = options.iter().map(|option| option.to_string()).collect();
+        self
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -73,11 +266,17 @@ pub struct ParserDescriptor {
     pub label: String,
     pub kind: String,
     pub configurable: bool,
+    pub params: Vec<ParamSchema>,
 }
 
-pub fn now_millis() -> u128 {
-    SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap_or_default()
-        .as_millis()
+impl ParserDescriptor {
+    pub fn stage(id: &str, label: &str, params: Vec<ParamSchema>) -> Self {
+        Self {
+            id: id.to_string(),
+            label: label.to_string(),
+            kind: "stage".to_string(),
+            configurable: !params.is_empty(),
+            params,
+        }
+    }
 }
@@ -4,20 +4,47 @@ mod pipeline;
 mod services;
 mod sources;
 
-use api::commands::{attach_pipeline, list_parsers, list_sources, mock_rx, start_source, stop_source};
-use services::AppState;
+use api::commands::{
+    attach_pipeline, configure_source, create_source, list_parsers, list_serial_ports,
+    list_sources, list_transmit_jobs, mock_rx, remove_source, send_data, set_backpressure,
+    set_hex_dump, set_source_encoding, start_source, start_transmit_job, stop_source,
+    stop_transmit_job, transact,
+};
+use services::{AppState, PortWatcher, SubscriptionHub};
+use tauri::Manager;
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
         .manage(AppState::new())
+        .setup(|app| {
+            let manager = app.state::<AppState>().manager.clone();
+            manager
+                .lock()
+                .map_err(|_| "lock poisoned".to_string())?
+                .set_hub(SubscriptionHub::new(app.handle().clone()))?;
+            PortWatcher::spawn(app.handle().clone(), manager)?;
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             list_sources,
             list_parsers,
+            list_serial_ports,
+            create_source,
+            remove_source,
             start_source,
             stop_source,
+            configure_source,
+            set_backpressure,
+            set_source_encoding,
+            set_hex_dump,
             attach_pipeline,
+            send_data,
+            transact,
+            list_transmit_jobs,
+            start_transmit_job,
+            stop_transmit_job,
             mock_rx
         ])
         .run(tauri::generate_context!())
@@ -0,0 +1,344 @@
+// ANSI 转义序列处理：去掉 CSI/OSC 等控制序列，可选地把 SGR 颜色转换为 DataPacket.spans
+use std::collections::HashMap;
+
+use serde_json::json;
+
+use crate::core::types::{
+    DataPacket, ParamSchema, ParamType, ParserDescriptor, PipelineItem, TextSpan, TextStyle,
+    Timestamp,
+};
+use crate::pipeline::params::StageParams;
+use crate::pipeline::stages::Stage;
+
+const ESC: char = '\u{1b}';
+const CSI_8BIT: char = '\u{9b}';
+const BEL: char = '\u{07}';
+
+// 未结束的转义序列最多缓存这么多字符，超出或空闲超时后去掉引导符，其余按普通文本输出
+const MAX_PENDING_ESCAPE: usize = 256;
+const PENDING_ESCAPE_IDLE_MS: u64 = 100;
+
+const COLOR_NAMES: [&str; 8] = [
+    "black", "red", "green", "yellow", "blue", "magenta", "cyan", "white",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnsiMode {
+    Strip,
+    Spans,
+}
+
+// 颜色状态会跨行延续，转义序列也可能被拆在两个包之间，因此按 (source_id, tags) 保存
+#[derive(Default)]
+struct AnsiState {
+    style: TextStyle,
+    partial: String,
+    // 最近一次收到数据的时间，用于冲刷残留的 partial
+    last_ts: Timestamp,
+}
+
+pub struct AnsiStage {
+    pub mode: AnsiMode,
+    states: HashMap<(String, Vec<String>), AnsiState>,
+}
+
+impl AnsiStage {
+    pub fn descriptor() -> ParserDescriptor {
+        ParserDescriptor::stage(
+            "ansi",
+            "ANSI Escapes",
+            vec![ParamSchema::new(
+                "mode",
+                ParamType::Enum,
+                json!("spans"),
+                "`strip` removes escape sequences, `spans` also turns SGR colours into text spans",
+            )
+            .options(&["strip", "spans"])],
+        )
+    }
+
+    pub fn build(params: &StageParams) -> Result<Box<dyn Stage + Send + Sync>, String> {
+        let mode = match params.get_str("mode") {
+            Some("strip") => AnsiMode::Strip,
+            _ => AnsiMode::Spans,
+        };
+        Ok(Box::new(AnsiStage::new(mode)))
+    }
+
+    pub fn new(mode: AnsiMode) -> Self {
+        Self {
+            mode,
+            states: HashMap::new(),
+        }
+    }
+
+    fn convert(&mut self, mut packet: DataPacket) -> DataPacket {
+        let Some(text) = packet.text.take() else {
+            return packet;
+        };
+        let state = self
+            .states
+            .entry((packet.source_id.clone(), packet.tags.clone()))
+            .or_default();
+        state.last_ts = packet.ts;
+        let input: Vec<char> = state.partial.drain(..).chain(text.chars()).collect();
+
+        let mut out = String::with_capacity(input.len());
+        let mut out_len = 0;
+        let mut span_start = 0;
+        let mut i = 0;
+        while i < input.len() {
+            let c = input[i];
+            if c != ESC && c != CSI_8BIT {
+                out.push(c);
+                out_len += 1;
+                i += 1;
+                continue;
+            }
+            let Some((end, sgr)) = scan_escape(&input, i) else {
+                if input.len() - i > MAX_PENDING_ESCAPE {
+                    i += 1;
+                    continue;
+                }
+                state.partial = input[i..].iter().collect();
+                break;
+            };
+            if let (Some(params), AnsiMode::Spans) = (sgr, self.mode) {
+                let mut style = state.style.clone();
+                apply_sgr(&mut style, &params);
+                if style != state.style {
+                    push_span(&mut packet.spans, span_start, out_len, &state.style);
+                    span_start = out_len;
+                    state.style = style;
+                }
+            }
+            i = end;
+        }
+        if self.mode == AnsiMode::Spans {
+            push_span(&mut packet.spans, span_start, out_len, &state.style);
+        }
+
+        packet.text = Some(out);
+        packet
+    }
+}
+
+impl Stage for AnsiStage {
+    fn name(&self) -> &str {
+        "ansi"
+    }
+
+    fn process(&mut self, item: PipelineItem) -> Vec<PipelineItem> {
+        match item {
+            PipelineItem::Packet(packet) => vec![PipelineItem::Packet(self.convert(packet))],
+            other => vec![other],
+        }
+    }
+
+    fn tick(&mut self, source_id: &str, now: Timestamp) -> Vec<PipelineItem> {
+        let mode = self.mode;
+        self.states
+            .iter_mut()
+            .filter(|((id, _), state)| {
+                id == source_id
+                    && !state.partial.is_empty()
+                    && now.mono_micros.saturating_sub(state.last_ts.mono_micros)
+                        >= PENDING_ESCAPE_IDLE_MS * 1000
+            })
+            .filter_map(|((id, tags), state)| {
+                let text: String = state.partial.drain(..).skip(1).collect();
+                if text.is_empty() {
+                    return None;
+                }
+                let mut packet = DataPacket {
+                    ts: state.last_ts,
+                    source_id: id.clone(),
+                    raw: text.as_bytes().to_vec(),
+                    text: None,
+                    tags: tags.clone(),
+                    spans: Vec::new(),
+                };
+                if mode == AnsiMode::Spans {
+                    push_span(&mut packet.spans, 0, text.chars().count(), &state.style);
+                }
+                packet.text = Some(text);
+                Some(PipelineItem::Packet(packet))
+            })
+            .collect()
+    }
+}
+
+fn push_span(spans: &mut Vec<TextSpan>, start: usize, end: usize, style: &TextStyle) {
+    if end > start && *style != TextStyle::default() {
+        spans.push(TextSpan {
+            start,
+            end,
+            style: style.clone(),
+        });
+    }
+}
+
+// 返回序列结束后的下标；若是 SGR（CSI ... m）同时返回参数。序列不完整时返回 None
+fn scan_escape(input: &[char], start: usize) -> Option<(usize, Option<Vec<u16>>)> {
+    let (csi_start, kind) = if input[start] == CSI_8BIT {
+        (start + 1, '[')
+    } else {
+        (start + 2, *input.get(start + 1)?)
+    };
+    match kind {
+        '[' => {
+            let mut i = csi_start;
+            loop {
+                let c = *input.get(i)?;
+                i += 1;
+                if ('\u{40}'..='\u{7e}').contains(&c) {
+                    let sgr = (c == 'm').then(|| {
+                        input[csi_start..i - 1]
+                            .iter()
+                            .collect::<String>()
+                            .split([';', ':'])
+                            .map(|param| param.parse().unwrap_or(0))
+                            .collect()
+                    });
+                    return Some((i, sgr));
+                }
+                // 非法字节说明序列已断，丢弃到这里为止
+                if !('\u{20}'..='\u{3f}').contains(&c) {
+                    return Some((i, None));
+                }
+            }
+        }
+        // OSC 以 BEL 或 ESC \ 结束
+        ']' => {
+            let mut i = start + 2;
+            loop {
+                match *input.get(i)? {
+                    BEL => return Some((i + 1, None)),
+                    ESC if *input.get(i + 1)? == '\\' => return Some((i + 2, None)),
+                    _ => i += 1,
+                }
+            }
+        }
+        // 字符集选择等三字节序列，例如 ESC ( B
+        '(' | ')' | '#' => {
+            input.get(start + 2)?;
+            Some((start + 3, None))
+        }
+        _ => Some((start + 2, None)),
+    }
+}
+
+fn apply_sgr(style: &mut TextStyle, params: &[u16]) {
+    let mut i = 0;
+    while i < params.len() {
+        match params[i] {
+            0 => *style = TextStyle::default(),
+            1 => style.bold = true,
+            2 => style.dim = true,
+            3 => style.italic = true,
+            4 => style.underline = true,
+            7 => style.inverse = true,
+            22 => {
+                style.bold = false;
+                style.dim = false;
+            }
+            23 => style.italic = false,
+            24 => style.underline = false,
+            27 => style.inverse = false,
+            code @ 30..=37 => style.fg = Some(palette(code - 30)),
+            code @ 40..=47 => style.bg = Some(palette(code - 40)),
+            code @ 90..=97 => style.fg = Some(palette(code - 90 + 8)),
+            code @ 100..=107 => style.bg = Some(palette(code - 100 + 8)),
+            39 => style.fg = None,
+            49 => style.bg = None,
+            code @ (38 | 48) => {
+                let (color, used) = extended_color(&params[i + 1..]);
+                if let Some(color) = color {
+                    if code == 38 {
+                        style.fg = Some(color);
+                    } else {
+                        style.bg = Some(color);
+                    }
+                }
+                i += used;
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+}
+
+// 38;5;n 或 38;2;r;g;b，返回颜色以及消耗的参数个数
+fn extended_color(params: &[u16]) -> (Option<String>, usize) {
+    match params {
+        [5, index, ..] => (Some(palette(*index)), 2),
+        [2, r, g, b, ..] => (Some(rgb(*r, *g, *b)), 4),
+        _ => (None, params.len()),
+    }
+}
+
+fn palette(index: u16) -> String {
+    match index {
+        0..=7 => COLOR_NAMES[index as usize].to_string(),
+        8..=15 => format!("bright_{}", COLOR_NAMES[index as usize - 8]),
+        // 6x6x6 色立方
+        16..=231 => {
+            let level = |value: u16| if value == 0 { 0 } else { 55 + value * 40 };
+            let index = index - 16;
+            rgb(level(index / 36), level(index / 6 % 6), level(index % 6))
+        }
+        _ => {
+            let gray = 8 + (index.min(255) - 232) * 10;
+            rgb(gray, gray, gray)
+        }
+    }
+}
+
+fn rgb(r: u16, g: u16, b: u16) -> String {
+    format!("#{:02x}{:02x}{:02x}", r.min(255), g.min(255), b.min(255))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text_packet(text: &str) -> DataPacket {
+        DataPacket::new("s", text.as_bytes().to_vec(), Some(text.to_string()))
+    }
+
+    #[test]
+    fn sgr_split_across_packets_becomes_span() {
+        let mut stage = AnsiStage::new(AnsiMode::Spans);
+        let first = stage.convert(text_packet("ok \x1b[3"));
+        assert_eq!(first.text.as_deref(), Some("ok "));
+        let second = stage.convert(text_packet("1merr\x1b[0m"));
+        assert_eq!(second.text.as_deref(), Some("err"));
+        assert_eq!(second.spans.len(), 1);
+        assert_eq!(second.spans[0].style.fg.as_deref(), Some("red"));
+    }
+
+    #[test]
+    fn unterminated_escape_is_capped() {
+        let mut stage = AnsiStage::new(AnsiMode::Strip);
+        let osc = format!("\x1b]0;{}", "x".repeat(MAX_PENDING_ESCAPE));
+        let packet = stage.convert(text_packet(&osc));
+        assert_eq!(packet.text.unwrap(), osc[1..]);
+        assert!(stage.states.values().all(|state| state.partial.is_empty()));
+    }
+
+    #[test]
+    fn idle_tick_flushes_partial_escape() {
+        let mut stage = AnsiStage::new(AnsiMode::Strip);
+        let packet = stage.convert(text_packet("a\x1b[12"));
+        assert_eq!(packet.text.as_deref(), Some("a"));
+        let mut now = packet.ts;
+        assert!(stage.tick("s", now).is_empty());
+        now.mono_micros += PENDING_ESCAPE_IDLE_MS * 1000;
+        let items = stage.tick("s", now);
+        assert!(matches!(
+            &items[..],
+            [PipelineItem::Packet(packet)] if packet.text.as_deref() == Some("[12")
+        ));
+        assert!(stage.tick("s", now).is_empty());
+    }
+}
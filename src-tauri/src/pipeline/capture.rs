@@ -0,0 +1,189 @@
+// 正则捕获：命名分组的值能解析为数字时输出 Metric，否则写入 ParsedEvent.payload
+use regex::Regex;
+use serde_json::{json, Map, Value};
+
+use crate::core::types::{
+    DataPacket, Metric, ParamSchema, ParamType, ParsedEvent, ParserDescriptor, PipelineItem,
+};
+use crate::pipeline::params::StageParams;
+use crate::pipeline::stages::Stage;
+
+// 事件 payload 中记录命中的是第几个 pattern，不能再用作分组名
+const PATTERN_KEY: &str = "_pattern";
+
+pub struct RegexStage {
+    patterns: Vec<Regex>,
+    kind: String,
+}
+
+impl RegexStage {
+    pub fn descriptor() -> ParserDescriptor {
+        ParserDescriptor::stage(
+            "regex",
+            "Regex Capture",
+            vec![
+                ParamSchema::new(
+                    "patterns",
+                    ParamType::StringList,
+                    json!([]),
+                    "Patterns tried in order; named groups become metrics when numeric, event fields otherwise",
+                ),
+                ParamSchema::new(
+                    "kind",
+                    ParamType::String,
+                    json!("regex"),
+                    "Event kind for lines with textual captures",
+                ),
+            ],
+        )
+    }
+
+    pub fn build(params: &StageParams) -> Result<Box<dyn Stage + Send + Sync>, String> {
+        let kind = params.get_str("kind").unwrap_or_default();
+        if kind.is_empty() {
+            return Err("parameter `kind`: must not be empty".to_string());
+        }
+        let stage = RegexStage::new(&params.get_string_list("patterns"), kind)
+            .map_err(|err| format!("parameter `patterns`: {err}"))?;
+        Ok(Box::new(stage))
+    }
+
+    // 按顺序尝试，每行只取第一个匹配的 pattern
+    pub fn new(patterns: &[String], kind: impl Into<String>) -> Result<Self, String> {
+        if patterns.is_empty() {
+            return Err("at least one pattern is required".to_string());
+        }
+        let patterns = patterns
+            .iter()
+            .enumerate()
+            .map(|(index, pattern)| {
+                let regex = Regex::new(pattern).map_err(|err| format!("[{index}]: {err}"))?;
+                if regex.capture_names().flatten().next().is_none() {
+                    return Err(format!("[{index}]: no named capture groups"));
+                }
+                if regex
+                    .capture_names()
+                    .flatten()
+                    .any(|name| name == PATTERN_KEY)
+                {
+                    return Err(format!("[{index}]: group name `{PATTERN_KEY}` is reserved"));
+                }
+                Ok(regex)
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        Ok(Self {
+            patterns,
+            kind: kind.into(),
+        })
+    }
+
+    fn capture(&self, packet: DataPacket) -> Vec<PipelineItem> {
+        let Some(text) = packet.text.as_deref() else {
+            return vec![PipelineItem::Packet(packet)];
+        };
+        let Some((index, captures)) = self
+            .patterns
+            .iter()
+            .enumerate()
+            .find_map(|(index, regex)| regex.captures(text).map(|captures| (index, captures)))
+        else {
+            return vec![PipelineItem::Packet(packet)];
+        };
+
+        let mut out = Vec::new();
+        let mut fields = Map::new();
+        for name in self.patterns[index].capture_names().flatten() {
+            let Some(value) = captures.name(name).map(|value| value.as_str().trim()) else {
+                continue;
+            };
+            // "nan"、"inf" 也能解析为 f64，按文本处理
+            match value
+                .parse::<f64>()
+                .ok()
+                .filter(|number| number.is_finite())
+            {
+                Some(number) => out.push(PipelineItem::Metric(Metric::from_packet(
+                    &packet, name, number,
+                ))),
+                None => {
+                    fields.insert(name.to_string(), Value::String(value.to_string()));
+                }
+            }
+        }
+
+        if !fields.is_empty() {
+            fields.insert(PATTERN_KEY.to_string(), Value::from(index));
+            out.push(PipelineItem::Event(ParsedEvent {
+                ts: packet.ts,
+                kind: self.kind.clone(),
+                payload: Value::Object(fields),
+            }));
+        }
+        out
+    }
+}
+
+impl Stage for RegexStage {
+    fn name(&self) -> &str {
+        "regex"
+    }
+
+    fn process(&mut self, item: PipelineItem) -> Vec<PipelineItem> {
+        match item {
+            PipelineItem::Packet(packet) => self.capture(packet),
+            other => vec![other],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn capture(stage: &RegexStage, text: &str) -> Vec<PipelineItem> {
+        stage.capture(DataPacket::new(
+            "s",
+            text.as_bytes().to_vec(),
+            Some(text.to_string()),
+        ))
+    }
+
+    #[test]
+    fn user_group_named_pattern_is_kept() {
+        let stage = RegexStage::new(
+            &[
+                r"^temp=(?P<temp>\S+)$".to_string(),
+                r"^(?P<pattern>\w+) (?P<value>\S+)$".to_string(),
+            ],
+            "regex",
+        )
+        .unwrap();
+        let items = capture(&stage, "mode nan");
+        let PipelineItem::Event(event) = &items[0] else {
+            panic!("expected an event");
+        };
+        assert_eq!(event.payload["pattern"], "mode");
+        assert_eq!(event.payload["value"], "nan");
+        assert_eq!(event.payload[PATTERN_KEY], 1);
+    }
+
+    #[test]
+    fn non_finite_values_are_not_metrics() {
+        let stage = RegexStage::new(&[r"^temp=(?P<temp>\S+)$".to_string()], "regex").unwrap();
+        assert!(matches!(
+            capture(&stage, "temp=21.5")[0],
+            PipelineItem::Metric(ref metric) if metric.value == 21.5
+        ));
+        for text in ["temp=inf", "temp=NaN", "temp=-infinity"] {
+            assert!(
+                matches!(capture(&stage, text)[0], PipelineItem::Event(_)),
+                "{text}"
+            );
+        }
+    }
+
+    #[test]
+    fn reserved_group_name_is_rejected() {
+        assert!(RegexStage::new(&[r"(?P<_pattern>\d+)".to_string()], "regex").is_err());
+    }
+}
@@ -0,0 +1,352 @@
+// CSV 行解析：识别表头（或使用配置的列名），按 source 记住表头，每行输出一组共享时间戳的 Metric
+use std::collections::HashMap;
+
+use serde_json::{json, Value};
+
+use crate::core::types::{
+    DataPacket, Metric, ParamSchema, ParamType, ParserDescriptor, PipelineItem, Timestamp,
+};
+use crate::pipeline::params::StageParams;
+use crate::pipeline::stages::Stage;
+
+// 已有表头时，只有空闲这么久之后（通常是设备复位）出现的文本行才可能是新表头
+const HEADER_RESET_IDLE_MS: u64 = 1000;
+// 候选表头行最多扣留这么久，之后由 tick 原样下发，仍可被下一行数据确认为表头
+const CANDIDATE_HOLD_MS: u64 = 200;
+
+// 尚未确认的表头：等下一行数据的列数一致才当作表头
+struct Candidate {
+    // 还没下发的原始行；tick 超时下发后为 None
+    packet: Option<DataPacket>,
+    cells: Vec<String>,
+}
+
+#[derive(Default)]
+struct CsvState {
+    header: Vec<String>,
+    candidate: Option<Candidate>,
+    last_ts: Option<Timestamp>,
+}
+
+pub struct CsvStage {
+    pub delimiter: char,
+    // 非空时固定使用这些列名，不再从数据里识别表头
+    pub columns: Vec<String>,
+    // 作为设备时间戳的列，不单独输出为 Metric
+    pub timestamp_column: Option<String>,
+    states: HashMap<(String, Vec<String>), CsvState>,
+}
+
+impl Default for CsvStage {
+    fn default() -> Self {
+        Self {
+            delimiter: ',',
+            columns: Vec::new(),
+            timestamp_column: None,
+            states: HashMap::new(),
+        }
+    }
+}
+
+impl CsvStage {
+    pub fn descriptor() -> ParserDescriptor {
+        ParserDescriptor::stage(
+            "csv",
+            "CSV",
+            vec![
+                ParamSchema::new(
+                    "delimiter",
+                    ParamType::String,
+                    json!(","),
+                    "Single-character column delimiter; use \\t for tabs",
+                ),
+                ParamSchema::new(
+                    "columns",
+                    ParamType::StringList,
+                    json!([]),
+                    "Fixed column names; empty means detect the header line per source",
+                ),
+                ParamSchema::new(
+                    "timestamp_column",
+                    ParamType::String,
+                    Value::Null,
+                    "Column holding the device-side timestamp, attached to each metric of the row",
+                ),
+            ],
+        )
+    }
+
+    pub fn build(params: &StageParams) -> Result<Box<dyn Stage + Send + Sync>, String> {
+        let delimiter = match params.get_str("delimiter").unwrap_or_default() {
+            "\\t" => '\t',
+            delimiter => {
+                let mut chars = delimiter.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) if c != '"' => c,
+                    _ => {
+                        return Err("parameter `delimiter`: must be a single character".to_string())
+                    }
+                }
+            }
+        };
+        Ok(Box::new(CsvStage {
+            delimiter,
+            columns: params.get_string_list("columns"),
+            timestamp_column: params
+                .get_str("timestamp_column")
+                .filter(|column| !column.is_empty())
+                .map(str::to_string),
+            ..CsvStage::default()
+        }))
+    }
+
+    fn parse(&mut self, packet: DataPacket) -> Vec<PipelineItem> {
+        let Some(text) = packet.text.as_deref() else {
+            return vec![PipelineItem::Packet(packet)];
+        };
+        let cells = split_row(text.trim(), self.delimiter);
+        if cells.iter().all(|cell| cell.is_empty()) {
+            return Vec::new();
+        }
+        let state = self
+            .states
+            .entry((packet.source_id.clone(), packet.tags.clone()))
+            .or_default();
+        let idle = state.last_ts.is_none_or(|last| {
+            packet.ts.mono_micros.saturating_sub(last.mono_micros) >= HEADER_RESET_IDLE_MS * 1000
+        });
+        state.last_ts = Some(packet.ts);
+
+        let mut out = Vec::new();
+        let candidate = state.candidate.take();
+        if cells.iter().all(|cell| parse_number(cell).is_none()) {
+            let header = if self.columns.is_empty() {
+                &state.header
+            } else {
+                &self.columns
+            };
+            out.extend(
+                candidate
+                    .and_then(|candidate| candidate.packet)
+                    .map(PipelineItem::Packet),
+            );
+            // 重复打印的表头直接吞掉；还没有表头或空闲后（设备复位）的文本行作为候选表头扣留
+            if cells == *header {
+                return out;
+            }
+            if self.columns.is_empty() && (state.header.is_empty() || idle) {
+                state.candidate = Some(Candidate {
+                    packet: Some(packet),
+                    cells,
+                });
+            } else {
+                out.push(PipelineItem::Packet(packet));
+            }
+            return out;
+        }
+        if let Some(candidate) = candidate {
+            if candidate.cells.len() == cells.len() {
+                state.header = candidate.cells;
+            } else {
+                out.extend(candidate.packet.map(PipelineItem::Packet));
+            }
+        }
+
+        let header = if self.columns.is_empty() {
+            state.header.as_slice()
+        } else {
+            self.columns.as_slice()
+        };
+        let name_of = |index: usize| {
+            header
+                .get(index)
+                .filter(|name| !name.is_empty())
+                .cloned()
+                .unwrap_or_else(|| format!("col{index}"))
+        };
+
+        let mut device_ts = None;
+        let mut values = Vec::with_capacity(cells.len());
+        for (index, cell) in cells.iter().enumerate() {
+            let Some(value) = parse_number(cell) else {
+                continue;
+            };
+            let name = name_of(index);
+            if self.timestamp_column.as_deref() == Some(name.as_str()) {
+                device_ts = Some(value);
+            } else {
+                values.push((name, value));
+            }
+        }
+
+        out.extend(values.into_iter().map(|(name, value)| {
+            let mut metric = Metric::from_packet(&packet, name, value);
+            metric.device_ts = device_ts;
+            PipelineItem::Metric(metric)
+        }));
+        out
+    }
+}
+
+impl Stage for CsvStage {
+    fn name(&self) -> &str {
+        "csv"
+    }
+
+    fn process(&mut self, item: PipelineItem) -> Vec<PipelineItem> {
+        match item {
+            PipelineItem::Packet(packet) => self.parse(packet),
+            other => vec![other],
+        }
+    }
+
+    // 数据流停下时把扣留的候选表头行放出去，避免最后一行文本一直收不到
+    fn tick(&mut self, source_id: &str, now: Timestamp) -> Vec<PipelineItem> {
+        self.states
+            .iter_mut()
+            .filter(|((id, _), state)| {
+                id == source_id
+                    && state.last_ts.is_some_and(|last| {
+                        now.mono_micros.saturating_sub(last.mono_micros) >= CANDIDATE_HOLD_MS * 1000
+                    })
+            })
+            .filter_map(|(_, state)| state.candidate.as_mut()?.packet.take())
+            .map(PipelineItem::Packet)
+            .collect()
+    }
+}
+
+// "nan"、"inf" 也能解析为 f64，不算数字
+fn parse_number(cell: &str) -> Option<f64> {
+    cell.parse::<f64>().ok().filter(|value| value.is_finite())
+}
+
+// 支持双引号包裹的单元格（内部 "" 表示一个引号）
+fn split_row(line: &str, delimiter: char) -> Vec<String> {
+    let mut cells = Vec::new();
+    let mut cell = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        if quoted {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    cell.push('"');
+                    chars.next();
+                }
+                '"' => quoted = false,
+                _ => cell.push(c),
+            }
+        } else if c == '"' && cell.trim().is_empty() {
+            cell.clear();
+            quoted = true;
+        } else if c == delimiter {
+            cells.push(cell.trim().to_string());
+            cell.clear();
+        } else {
+            cell.push(c);
+        }
+    }
+    cells.push(cell.trim().to_string());
+    cells
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_at(stage: &mut CsvStage, line: &str, millis: u64) -> Vec<PipelineItem> {
+        let ts = Timestamp {
+            mono_micros: millis * 1000,
+            unix_micros: millis * 1000,
+        };
+        stage.parse(
+            DataPacket::new("s", line.as_bytes().to_vec(), Some(line.to_string()))
+                .with_timestamp(ts),
+        )
+    }
+
+    fn parse(stage: &mut CsvStage, line: &str) -> Vec<PipelineItem> {
+        parse_at(stage, line, 0)
+    }
+
+    fn metric_names(items: &[PipelineItem]) -> Vec<String> {
+        items
+            .iter()
+            .filter_map(|item| match item {
+                PipelineItem::Metric(metric) => Some(metric.name.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn passed_text(items: &[PipelineItem]) -> Vec<&str> {
+        items
+            .iter()
+            .filter_map(|item| match item {
+                PipelineItem::Packet(packet) => packet.text.as_deref(),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn header_is_confirmed_by_matching_row() {
+        let mut stage = CsvStage::default();
+        assert!(parse(&mut stage, "temp,humidity").is_empty());
+        assert_eq!(
+            metric_names(&parse(&mut stage, "21.5,40")),
+            vec!["temp", "humidity"]
+        );
+    }
+
+    #[test]
+    fn text_lines_after_header_pass_through_immediately() {
+        let mut stage = CsvStage::default();
+        parse(&mut stage, "t,ax");
+        parse(&mut stage, "1,2");
+        // 列数与数据行相同的文本行也不能替换已确认的表头
+        for line in ["boot ok, ready", "sensor reset, retrying, please wait"] {
+            assert_eq!(passed_text(&parse(&mut stage, line)), vec![line]);
+        }
+        assert_eq!(metric_names(&parse(&mut stage, "3,4")), vec!["t", "ax"]);
+        // 重新打印的同一表头被吞掉
+        assert!(parse(&mut stage, "t,ax").is_empty());
+        assert_eq!(metric_names(&parse(&mut stage, "5,6")), vec!["t", "ax"]);
+    }
+
+    #[test]
+    fn new_header_after_idle_gap_replaces_old_one() {
+        let mut stage = CsvStage::default();
+        parse_at(&mut stage, "t,ax", 0);
+        parse_at(&mut stage, "1,2", 10);
+        assert!(parse_at(&mut stage, "time,ay", 2000).is_empty());
+        assert_eq!(
+            metric_names(&parse_at(&mut stage, "3,4", 2010)),
+            vec!["time", "ay"]
+        );
+    }
+
+    #[test]
+    fn tick_releases_held_candidate() {
+        let mut stage = CsvStage::default();
+        assert!(parse_at(&mut stage, "last log line", 0).is_empty());
+        let idle = |millis: u64| Timestamp {
+            mono_micros: millis * 1000,
+            unix_micros: millis * 1000,
+        };
+        assert!(stage.tick("s", idle(50)).is_empty());
+        assert_eq!(
+            passed_text(&stage.tick("s", idle(300))),
+            vec!["last log line"]
+        );
+        assert!(stage.tick("s", idle(600)).is_empty());
+    }
+
+    #[test]
+    fn non_finite_cells_are_not_numbers() {
+        let mut stage = CsvStage::default();
+        parse(&mut stage, "nan,inf");
+        assert_eq!(metric_names(&parse(&mut stage, "1,2")), vec!["nan", "inf"]);
+    }
+}
@@ -0,0 +1,86 @@
+// 按指定编码把 DataPacket.raw 解码到 text，每个 (source_id, tags) 各用一个流式解码器
+use std::collections::HashMap;
+
+use serde_json::json;
+
+use crate::core::encoding::{EncodingConfig, InvalidBytes, TextDecoder, TextEncoding};
+use crate::core::types::{DataPacket, ParamSchema, ParamType, ParserDescriptor, PipelineItem};
+use crate::pipeline::params::StageParams;
+use crate::pipeline::stages::Stage;
+
+pub struct DecodeStage {
+    config: EncodingConfig,
+    decoders: HashMap<(String, Vec<String>), TextDecoder>,
+}
+
+impl DecodeStage {
+    pub fn descriptor() -> ParserDescriptor {
+        ParserDescriptor::stage(
+            "decode",
+            "Text Decoder",
+            vec![
+                ParamSchema::new(
+                    "encoding",
+                    ParamType::Enum,
+                    json!("utf8"),
+                    "Character encoding of raw bytes; gbk also covers GB2312, auto tries UTF-8 and falls back to GBK",
+                )
+                .options(&["utf8", "latin1", "gbk", "auto"]),
+                ParamSchema::new(
+                    "invalid",
+                    ParamType::Enum,
+                    json!("lossy"),
+                    "Invalid bytes become U+FFFD (`lossy`) or \\xNN (`hex`)",
+                )
+                .options(&["lossy", "hex"]),
+            ],
+        )
+    }
+
+    pub fn build(params: &StageParams) -> Result<Box<dyn Stage + Send + Sync>, String> {
+        let config = EncodingConfig {
+            encoding: match params.get_str("encoding") {
+                Some("latin1") => TextEncoding::Latin1,
+                Some("gbk") => TextEncoding::Gbk,
+                Some("auto") => TextEncoding::Auto,
+                _ => TextEncoding::Utf8,
+            },
+            invalid: match params.get_str("invalid") {
+                Some("hex") => InvalidBytes::Hex,
+                _ => InvalidBytes::Lossy,
+            },
+        };
+        Ok(Box::new(DecodeStage::new(config)))
+    }
+
+    pub fn new(config: EncodingConfig) -> Self {
+        Self {
+            config,
+            decoders: HashMap::new(),
+        }
+    }
+
+    fn decode(&mut self, mut packet: DataPacket) -> DataPacket {
+        let config = self.config;
+        let decoder = self
+            .decoders
+            .entry((packet.source_id.clone(), packet.tags.clone()))
+            .or_insert_with(|| TextDecoder::new(config));
+        packet.text = Some(decoder.decode(&packet.raw));
+        packet.spans.clear();
+        packet
+    }
+}
+
+impl Stage for DecodeStage {
+    fn name(&self) -> &str {
+        "decode"
+    }
+
+    fn process(&mut self, item: PipelineItem) -> Vec<PipelineItem> {
+        match item {
+            PipelineItem::Packet(packet) => vec![PipelineItem::Packet(self.decode(packet))],
+            other => vec![other],
+        }
+    }
+}
@@ -0,0 +1,694 @@
+// 基于 DataPacket.raw 的二进制分帧：跨包缓冲，每个完整帧输出一个 packet，
+// 坏帧输出 frame_error 事件和该 source 累计坏帧数的 <stage>.malformed_frames 指标
+use std::collections::HashMap;
+
+use serde_json::{json, Value};
+
+use crate::core::types::{
+    DataPacket, Metric, ParamSchema, ParamType, ParsedEvent, ParserDescriptor, PipelineItem,
+    Timestamp,
+};
+use crate::pipeline::params::StageParams;
+use crate::pipeline::stages::Stage;
+
+pub const DEFAULT_MAX_FRAME_LENGTH: usize = 65_536;
+
+const SLIP_END: u8 = 0xC0;
+const SLIP_ESC: u8 = 0xDB;
+const SLIP_ESC_END: u8 = 0xDC;
+const SLIP_ESC_ESC: u8 = 0xDD;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Endian {
+    Little,
+    Big,
+}
+
+impl Endian {
+    // 按字节序把最多 8 个字节拼成无符号整数
+    pub fn read_uint(self, bytes: &[u8]) -> u64 {
+        let fold = |acc: u64, byte: &u8| (acc << 8) | u64::from(*byte);
+        match self {
+            Endian::Little => bytes.iter().rev().fold(0, fold),
+            Endian::Big => bytes.iter().fold(0, fold),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FrameFormat {
+    Slip,
+    // 以 0x00 分隔的 COBS 编码帧
+    Cobs,
+    Fixed {
+        length: usize,
+    },
+    // 长度字段位于 offset 处，值 + adjust 为其后负载的字节数
+    LengthPrefix {
+        offset: usize,
+        width: usize,
+        endian: Endian,
+        adjust: i64,
+        include_header: bool,
+    },
+    // escape 之后的字节按 escape_xor 还原（HDLC 为 0x20，简单转义为 0）
+    Delimited {
+        start: u8,
+        end: u8,
+        escape: Option<u8>,
+        escape_xor: u8,
+    },
+}
+
+enum Scan {
+    // 从缓冲起点开始共消耗 consumed 字节
+    Frame {
+        payload: Vec<u8>,
+        consumed: usize,
+    },
+    Malformed {
+        consumed: usize,
+        reason: &'static str,
+    },
+    Skip(usize),
+    Incomplete,
+}
+
+impl FrameFormat {
+    pub fn name(&self) -> &'static str {
+        match self {
+            FrameFormat::Slip => "slip",
+            FrameFormat::Cobs => "cobs",
+            FrameFormat::Fixed { .. } => "fixed_frame",
+            FrameFormat::LengthPrefix { .. } => "length_prefix_frame",
+            FrameFormat::Delimited { .. } => "delimited_frame",
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            FrameFormat::Fixed { length: 0 } => Err("frame length must be greater than 0".into()),
+            FrameFormat::LengthPrefix { width, .. } if ![1, 2, 4].contains(width) => {
+                Err(format!("unsupported length width: {width}"))
+            }
+            FrameFormat::Delimited {
+                start,
+                end,
+                escape: Some(escape),
+                ..
+            } if escape == start || escape == end => {
+                Err("escape byte must differ from the delimiters".into())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn scan(&self, buf: &[u8], max_frame_length: usize) -> Scan {
+        if buf.is_empty() {
+            return Scan::Incomplete;
+        }
+        match self {
+            FrameFormat::Slip => scan_slip(buf),
+            FrameFormat::Cobs => scan_cobs(buf),
+            FrameFormat::Fixed { length } => match buf.get(..*length) {
+                Some(frame) => Scan::Frame {
+                    payload: frame.to_vec(),
+                    consumed: *length,
+                },
+                None => Scan::Incomplete,
+            },
+            FrameFormat::LengthPrefix {
+                offset,
+                width,
+                endian,
+                adjust,
+                include_header,
+            } => {
+                let header = offset + width;
+                let Some(field) = buf.get(*offset..header) else {
+                    return Scan::Incomplete;
+                };
+                let length = endian.read_uint(field) as i64 + adjust;
+                if length < 0 || length as usize + header > max_frame_length {
+                    // 长度字段不可信，只丢一个字节重新同步
+                    return Scan::Malformed {
+                        consumed: 1,
+                        reason: "invalid length",
+                    };
+                }
+                let frame_end = header + length as usize;
+                let Some(frame) = buf.get(..frame_end) else {
+                    return Scan::Incomplete;
+                };
+                let payload = if *include_header {
+                    frame
+                } else {
+                    &frame[header..]
+                };
+                Scan::Frame {
+                    payload: payload.to_vec(),
+                    consumed: frame_end,
+                }
+            }
+            FrameFormat::Delimited {
+                start,
+                end,
+                escape,
+                escape_xor,
+            } => scan_delimited(buf, *start, *end, *escape, *escape_xor),
+        }
+    }
+}
+
+fn scan_slip(buf: &[u8]) -> Scan {
+    let Some(end) = buf.iter().position(|byte| *byte == SLIP_END) else {
+        return Scan::Incomplete;
+    };
+    // 帧前导的 END 只用来冲掉线路噪声
+    if end == 0 {
+        return Scan::Skip(1);
+    }
+    let mut payload = Vec::with_capacity(end);
+    let mut bytes = buf[..end].iter();
+    while let Some(&byte) = bytes.next() {
+        if byte != SLIP_ESC {
+            payload.push(byte);
+            continue;
+        }
+        match bytes.next() {
+            Some(&SLIP_ESC_END) => payload.push(SLIP_END),
+            Some(&SLIP_ESC_ESC) => payload.push(SLIP_ESC),
+            _ => {
+                return Scan::Malformed {
+                    consumed: end + 1,
+                    reason: "invalid escape",
+                }
+            }
+        }
+    }
+    Scan::Frame {
+        payload,
+        consumed: end + 1,
+    }
+}
+
+fn scan_cobs(buf: &[u8]) -> Scan {
+    let Some(end) = buf.iter().position(|byte| *byte == 0) else {
+        return Scan::Incomplete;
+    };
+    if end == 0 {
+        return Scan::Skip(1);
+    }
+    let data = &buf[..end];
+    let mut payload = Vec::with_capacity(end);
+    let mut i = 0;
+    while i < data.len() {
+        let code = data[i] as usize;
+        let block_end = i + code;
+        if block_end > data.len() {
+            return Scan::Malformed {
+                consumed: end + 1,
+                reason: "truncated block",
+            };
+        }
+        payload.extend_from_slice(&data[i + 1..block_end]);
+        i = block_end;
+        if code < 0xFF && i < data.len() {
+            payload.push(0);
+        }
+    }
+    Scan::Frame {
+        payload,
+        consumed: end + 1,
+    }
+}
+
+fn scan_delimited(buf: &[u8], start: u8, end: u8, escape: Option<u8>, escape_xor: u8) -> Scan {
+    match buf.iter().position(|byte| *byte == start) {
+        None => return Scan::Skip(buf.len()),
+        Some(0) => {}
+        Some(noise) => return Scan::Skip(noise),
+    }
+
+    let mut payload = Vec::new();
+    let mut i = 1;
+    while i < buf.len() {
+        let byte = buf[i];
+        if Some(byte) == escape {
+            let Some(escaped) = buf.get(i + 1) else {
+                return Scan::Incomplete;
+            };
+            payload.push(escaped ^ escape_xor);
+            i += 2;
+            continue;
+        }
+        if byte == end {
+            // 起止符相同（如 HDLC 的 0x7E）时，结束符同时作为下一帧的起始符，不消耗
+            let consumed = if start == end { i } else { i + 1 };
+            if payload.is_empty() && start == end {
+                return Scan::Skip(consumed);
+            }
+            return Scan::Frame { payload, consumed };
+        }
+        if byte == start {
+            return Scan::Malformed {
+                consumed: i,
+                reason: "unexpected start delimiter",
+            };
+        }
+        payload.push(byte);
+        i += 1;
+    }
+    Scan::Incomplete
+}
+
+// 尚未凑成完整帧的字节，按 (source_id, tags) 分别缓冲
+struct PendingBytes {
+    raw: Vec<u8>,
+    // 缓冲中第一个字节所在包的时间戳
+    ts: Timestamp,
+}
+
+pub struct FramingStage {
+    format: FrameFormat,
+    pub max_frame_length: usize,
+    // 按 source_id 累计的坏帧数
+    malformed: HashMap<String, u64>,
+    pending: HashMap<(String, Vec<String>), PendingBytes>,
+}
+
+impl FramingStage {
+    pub fn slip_descriptor() -> ParserDescriptor {
+        ParserDescriptor::stage("slip", "SLIP Decoder", vec![max_frame_length_param()])
+    }
+
+    pub fn build_slip(params: &StageParams) -> Result<Box<dyn Stage + Send + Sync>, String> {
+        framing_stage(FrameFormat::Slip, params)
+    }
+
+    pub fn cobs_descriptor() -> ParserDescriptor {
+        ParserDescriptor::stage("cobs", "COBS Decoder", vec![max_frame_length_param()])
+    }
+
+    pub fn build_cobs(params: &StageParams) -> Result<Box<dyn Stage + Send + Sync>, String> {
+        framing_stage(FrameFormat::Cobs, params)
+    }
+
+    pub fn fixed_descriptor() -> ParserDescriptor {
+        ParserDescriptor::stage(
+            "fixed_frame",
+            "Fixed-Length Frames",
+            vec![
+                ParamSchema::new("length", ParamType::Integer, json!(16), "Bytes per frame")
+                    .range(1.0, DEFAULT_MAX_FRAME_LENGTH as f64),
+            ],
+        )
+    }
+
+    pub fn build_fixed(params: &StageParams) -> Result<Box<dyn Stage + Send + Sync>, String> {
+        let length = params.get_usize("length").unwrap_or_default();
+        framing_stage(FrameFormat::Fixed { length }, params)
+    }
+
+    pub fn length_prefix_descriptor() -> ParserDescriptor {
+        ParserDescriptor::stage(
+            "length_prefix_frame",
+            "Length-Prefixed Frames",
+            vec![
+                ParamSchema::new(
+                    "length_offset",
+                    ParamType::Integer,
+                    json!(0),
+                    "Header bytes before the length field",
+                )
+                .range(0.0, 255.0),
+                ParamSchema::new(
+                    "length_width",
+                    ParamType::Enum,
+                    json!("u16"),
+                    "Size of the length field",
+                )
+                .options(&["u8", "u16", "u32"]),
+                ParamSchema::new(
+                    "endian",
+                    ParamType::Enum,
+                    json!("le"),
+                    "Byte order of the length field",
+                )
+                .options(&["le", "be"]),
+                ParamSchema::new(
+                    "length_adjust",
+                    ParamType::Integer,
+                    json!(0),
+                    "Added to the length value, e.g. when it also counts the header or a CRC",
+                )
+                .range(-65_536.0, 65_536.0),
+                ParamSchema::new(
+                    "include_header",
+                    ParamType::Bool,
+                    json!(false),
+                    "Keep the header and length field in the emitted frame",
+                ),
+                max_frame_length_param(),
+            ],
+        )
+    }
+
+    pub fn build_length_prefix(
+        params: &StageParams,
+    ) -> Result<Box<dyn Stage + Send + Sync>, String> {
+        let format = FrameFormat::LengthPrefix {
+            offset: params.get_usize("length_offset").unwrap_or_default(),
+            width: match params.get_str("length_width") {
+                Some("u8") => 1,
+                Some("u32") => 4,
+                _ => 2,
+            },
+            endian: match params.get_str("endian") {
+                Some("be") => Endian::Big,
+                _ => Endian::Little,
+            },
+            adjust: params.get_i64("length_adjust").unwrap_or_default(),
+            include_header: params.get_bool("include_header"),
+        };
+        framing_stage(format, params)
+    }
+
+    pub fn delimited_descriptor() -> ParserDescriptor {
+        ParserDescriptor::stage(
+            "delimited_frame",
+            "Delimited Frames",
+            vec![
+                ParamSchema::new(
+                    "start_byte",
+                    ParamType::Integer,
+                    json!(0x02),
+                    "Byte that opens a frame",
+                )
+                .range(0.0, 255.0),
+                ParamSchema::new(
+                    "end_byte",
+                    ParamType::Integer,
+                    json!(0x03),
+                    "Byte that closes a frame; may equal start_byte as in HDLC",
+                )
+                .range(0.0, 255.0),
+                ParamSchema::new(
+                    "escape_byte",
+                    ParamType::Integer,
+                    Value::Null,
+                    "Byte that escapes the following byte; unset disables escaping",
+                )
+                .range(0.0, 255.0),
+                ParamSchema::new(
+                    "escape_xor",
+                    ParamType::Integer,
+                    json!(0),
+                    "XOR applied to escaped bytes (0x20 for HDLC)",
+                )
+                .range(0.0, 255.0),
+                max_frame_length_param(),
+            ],
+        )
+    }
+
+    pub fn build_delimited(params: &StageParams) -> Result<Box<dyn Stage + Send + Sync>, String> {
+        let byte = |name| params.get_i64(name).map(|value| value as u8);
+        let format = FrameFormat::Delimited {
+            start: byte("start_byte").unwrap_or_default(),
+            end: byte("end_byte").unwrap_or_default(),
+            escape: byte("escape_byte"),
+            escape_xor: byte("escape_xor").unwrap_or_default(),
+        };
+        framing_stage(format, params)
+    }
+
+    pub fn new(format: FrameFormat) -> Result<Self, String> {
+        format.validate()?;
+        Ok(Self {
+            format,
+            max_frame_length: DEFAULT_MAX_FRAME_LENGTH,
+            malformed: HashMap::new(),
+            pending: HashMap::new(),
+        })
+    }
+
+    fn feed(&mut self, packet: DataPacket) -> Vec<PipelineItem> {
+        let key = (packet.source_id.clone(), packet.tags.clone());
+        let (mut buf, pending_ts) = match self.pending.remove(&key) {
+            Some(pending) => (pending.raw, pending.ts),
+            None => (Vec::new(), packet.ts),
+        };
+        let carried = buf.len();
+        buf.extend_from_slice(&packet.raw);
+        let ts_at = |offset: usize| {
+            if offset < carried {
+                pending_ts
+            } else {
+                packet.ts
+            }
+        };
+
+        let mut out = Vec::new();
+        let mut offset = 0;
+        loop {
+            match self.format.scan(&buf[offset..], self.max_frame_length) {
+                Scan::Frame { payload, consumed } => {
+                    out.push(PipelineItem::Packet(DataPacket {
+                        ts: ts_at(offset),
+                        source_id: packet.source_id.clone(),
+                        raw: payload,
+                        text: None,
+                        tags: packet.tags.clone(),
+                        spans: Vec::new(),
+                    }));
+                    offset += consumed;
+                }
+                Scan::Malformed { consumed, reason } => {
+                    out.extend(self.malformed_frame(
+                        &packet.source_id,
+                        ts_at(offset),
+                        reason,
+                        consumed,
+                    ));
+                    offset += consumed;
+                }
+                Scan::Skip(skipped) => offset += skipped,
+                Scan::Incomplete => break,
+            }
+        }
+
+        let rest = buf.split_off(offset);
+        if rest.len() > self.max_frame_length {
+            out.extend(self.malformed_frame(
+                &packet.source_id,
+                ts_at(offset),
+                "frame too long",
+                rest.len(),
+            ));
+        } else if !rest.is_empty() {
+            self.pending.insert(
+                key,
+                PendingBytes {
+                    raw: rest,
+                    ts: ts_at(offset),
+                },
+            );
+        }
+        out
+    }
+
+    fn malformed_frame(
+        &mut self,
+        source_id: &str,
+        ts: Timestamp,
+        reason: &str,
+        length: usize,
+    ) -> [PipelineItem; 2] {
+        let count = self.malformed.entry(source_id.to_string()).or_default();
+        *count += 1;
+        let count = *count;
+        let stage = self.format.name();
+        [
+            PipelineItem::Event(ParsedEvent {
+                ts,
+                kind: "frame_error".to_string(),
+                payload: json!({
+                    "source_id": source_id,
+                    "stage": stage,
+                    "reason": reason,
+                    "length": length,
+                    "malformed_frames": count,
+                }),
+            }),
+            PipelineItem::Metric(Metric {
+                ts,
+                source_id: source_id.to_string(),
+                name: format!("{stage}.malformed_frames"),
+                value: count as f64,
+                device_ts: None,
+            }),
+        ]
+    }
+}
+
+fn max_frame_length_param() -> ParamSchema {
+    ParamSchema::new(
+        "max_frame_length",
+        ParamType::Integer,
+        json!(DEFAULT_MAX_FRAME_LENGTH),
+        "Drop buffered bytes that grow past this without forming a frame",
+    )
+    .range(1.0, 16_777_216.0)
+}
+
+fn framing_stage(
+    format: FrameFormat,
+    params: &StageParams,
+) -> Result<Box<dyn Stage + Send + Sync>, String> {
+    let mut stage = FramingStage::new(format)?;
+    stage.max_frame_length = params
+        .get_usize("max_frame_length")
+        .unwrap_or(DEFAULT_MAX_FRAME_LENGTH);
+    Ok(Box::new(stage))
+}
+
+impl Stage for FramingStage {
+    fn name(&self) -> &str {
+        self.format.name()
+    }
+
+    fn process(&mut self, item: PipelineItem) -> Vec<PipelineItem> {
+        match item {
+            PipelineItem::Packet(packet) => self.feed(packet),
+            other => vec![other],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn feed(stage: &mut FramingStage, raw: &[u8]) -> Vec<PipelineItem> {
+        stage.process(PipelineItem::Packet(DataPacket::new(
+            "s",
+            raw.to_vec(),
+            None,
+        )))
+    }
+
+    fn frames(items: &[PipelineItem]) -> Vec<Vec<u8>> {
+        items
+            .iter()
+            .filter_map(|item| match item {
+                PipelineItem::Packet(packet) => Some(packet.raw.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn slip_decodes_escapes() {
+        let mut stage = FramingStage::new(FrameFormat::Slip).unwrap();
+        let items = feed(
+            &mut stage,
+            &[
+                SLIP_END,
+                0x01,
+                SLIP_ESC,
+                SLIP_ESC_END,
+                SLIP_ESC,
+                SLIP_ESC_ESC,
+                0x02,
+                SLIP_END,
+            ],
+        );
+        assert_eq!(frames(&items), vec![vec![0x01, SLIP_END, SLIP_ESC, 0x02]]);
+    }
+
+    #[test]
+    fn cobs_decodes_zero_runs() {
+        let mut stage = FramingStage::new(FrameFormat::Cobs).unwrap();
+        let items = feed(
+            &mut stage,
+            &[0x03, 0x11, 0x22, 0x02, 0x33, 0x00, 0x01, 0x01, 0x00],
+        );
+        assert_eq!(
+            frames(&items),
+            vec![vec![0x11, 0x22, 0x00, 0x33], vec![0x00]]
+        );
+    }
+
+    #[test]
+    fn frames_join_across_packets() {
+        let cases = [
+            (
+                FrameFormat::Slip,
+                vec![0x01, SLIP_ESC, SLIP_ESC_END, 0x02, SLIP_END],
+                vec![0x01, SLIP_END, 0x02],
+            ),
+            (
+                FrameFormat::Cobs,
+                vec![0x03, 0x11, 0x22, 0x02, 0x33, 0x00],
+                vec![0x11, 0x22, 0x00, 0x33],
+            ),
+            (
+                FrameFormat::LengthPrefix {
+                    offset: 0,
+                    width: 2,
+                    endian: Endian::Big,
+                    adjust: 0,
+                    include_header: false,
+                },
+                vec![0x00, 0x03, 0xAA, 0xBB, 0xCC],
+                vec![0xAA, 0xBB, 0xCC],
+            ),
+            (
+                FrameFormat::Delimited {
+                    start: 0x7E,
+                    end: 0x7F,
+                    escape: Some(0x7D),
+                    escape_xor: 0x20,
+                },
+                vec![0x7E, 0x01, 0x7D, 0x5E, 0x02, 0x7F],
+                vec![0x01, 0x7E, 0x02],
+            ),
+        ];
+        for (format, encoded, decoded) in cases {
+            // 在每个位置切成两个包，结果都应与一次收到时相同
+            for cut in 1..encoded.len() {
+                let mut stage = FramingStage::new(format.clone()).unwrap();
+                let mut items = feed(&mut stage, &encoded[..cut]);
+                items.extend(feed(&mut stage, &encoded[cut..]));
+                assert_eq!(
+                    frames(&items),
+                    vec![decoded.clone()],
+                    "{} cut at {cut}",
+                    format.name()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn malformed_frames_are_counted_per_source() {
+        let mut stage = FramingStage::new(FrameFormat::Cobs).unwrap();
+        feed(&mut stage, &[0x05, 0x11, 0x00]);
+        let items = feed(&mut stage, &[0x05, 0x11, 0x00, 0x02, 0x22, 0x00]);
+        assert_eq!(frames(&items), vec![vec![0x22]]);
+        let metric = items
+            .iter()
+            .find_map(|item| match item {
+                PipelineItem::Metric(metric) => Some(metric),
+                _ => None,
+            })
+            .unwrap();
+        assert_eq!(metric.name, "cobs.malformed_frames");
+        assert_eq!(metric.value, 2.0);
+        assert!(items.iter().any(|item| matches!(
+            item,
+            PipelineItem::Event(event) if event.kind == "frame_error"
+        )));
+    }
+}
@@ -0,0 +1,252 @@
+// 每行一个 JSON 对象：整体作为 ParsedEvent 输出，数值叶子按点分路径展开为 Metric
+use serde_json::{json, Value};
+
+use crate::core::types::{
+    DataPacket, Metric, ParamSchema, ParamType, ParsedEvent, ParserDescriptor, PipelineItem,
+};
+use crate::pipeline::params::StageParams;
+use crate::pipeline::stages::Stage;
+
+pub struct JsonLineStage {
+    pub kind: String,
+    // 若对象里该路径是字符串，用它作为事件 kind，例如 "type" 或 "meta.kind"
+    pub kind_field: Option<String>,
+    pub metrics: bool,
+}
+
+impl Default for JsonLineStage {
+    fn default() -> Self {
+        Self {
+            kind: "json".to_string(),
+            kind_field: None,
+            metrics: true,
+        }
+    }
+}
+
+impl JsonLineStage {
+    pub fn descriptor() -> ParserDescriptor {
+        ParserDescriptor::stage(
+            "json_line",
+            "JSON Lines",
+            vec![
+                ParamSchema::new(
+                    "kind",
+                    ParamType::String,
+                    json!("json"),
+                    "Event kind for parsed objects",
+                ),
+                ParamSchema::new(
+                    "kind_field",
+                    ParamType::String,
+                    Value::Null,
+                    "Dotted path of a string field to use as the event kind, e.g. `type`",
+                ),
+                ParamSchema::new(
+                    "metrics",
+                    ParamType::Bool,
+                    json!(true),
+                    "Also emit numeric leaves as metrics named by their dotted path",
+                ),
+            ],
+        )
+    }
+
+    pub fn build(params: &StageParams) -> Result<Box<dyn Stage + Send + Sync>, String> {
+        let kind = params.get_str("kind").unwrap_or_default();
+        if kind.is_empty() {
+            return Err("parameter `kind`: must not be empty".to_string());
+        }
+        Ok(Box::new(JsonLineStage {
+            kind: kind.to_string(),
+            kind_field: params
+                .get_str("kind_field")
+                .filter(|field| !field.is_empty())
+                .map(str::to_string),
+            metrics: params.get_bool("metrics"),
+        }))
+    }
+
+    fn parse(&self, packet: DataPacket) -> Vec<PipelineItem> {
+        let object = match packet
+            .text
+            .as_deref()
+            .map(str::trim)
+            .filter(|text| text.starts_with('{'))
+            .and_then(|text| serde_json::from_str::<Value>(text).ok())
+        {
+            Some(object @ Value::Object(_)) => object,
+            // 解析失败的行原样放行
+            _ => return vec![PipelineItem::Packet(packet)],
+        };
+
+        let mut out = Vec::new();
+        if self.metrics {
+            let mut leaves = Vec::new();
+            flatten_numbers(&object, &mut String::new(), &mut leaves);
+            out.extend(leaves.into_iter().map(|(name, value)| {
+                PipelineItem::Metric(Metric::from_packet(&packet, name, value))
+            }));
+        }
+
+        let kind = self
+            .kind_field
+            .as_deref()
+            .and_then(|path| lookup(&object, path))
+            .and_then(Value::as_str)
+            .filter(|kind| !kind.is_empty())
+            .unwrap_or(&self.kind)
+            .to_string();
+        out.push(PipelineItem::Event(ParsedEvent {
+            ts: packet.ts,
+            kind,
+            payload: object,
+        }));
+        out
+    }
+}
+
+impl Stage for JsonLineStage {
+    fn name(&self) -> &str {
+        "json_line"
+    }
+
+    fn process(&mut self, item: PipelineItem) -> Vec<PipelineItem> {
+        match item {
+            PipelineItem::Packet(packet) => self.parse(packet),
+            other => vec![other],
+        }
+    }
+}
+
+// 路径写法与 Metric 名一致，数组用下标，例如 events.0.type
+fn lookup<'a>(value: &'a Value, path: &str) -> Option<&'a Value> {
+    path.split('.').try_fold(value, |value, key| match value {
+        Value::Array(items) => key.parse::<usize>().ok().and_then(|index| items.get(index)),
+        _ => value.get(key),
+    })
+}
+
+// 数组元素用下标作为路径段，例如 samples.0
+fn flatten_numbers(value: &Value, path: &mut String, out: &mut Vec<(String, f64)>) {
+    let mut descend = |key: &str, child: &Value, path: &mut String| {
+        let len = path.len();
+        if !path.is_empty() {
+            path.push('.');
+        }
+        path.push_str(key);
+        flatten_numbers(child, path, out);
+        path.truncate(len);
+    };
+    match value {
+        Value::Number(number) => {
+            if let Some(number) = number.as_f64() {
+                out.push((path.clone(), number));
+            }
+        }
+        Value::Object(map) => {
+            for (key, child) in map {
+                descend(key, child, path);
+            }
+        }
+        Value::Array(items) => {
+            for (index, child) in items.iter().enumerate() {
+                descend(&index.to_string(), child, path);
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(stage: &JsonLineStage, text: &str) -> Vec<PipelineItem> {
+        stage.parse(DataPacket::new(
+            "s",
+            text.as_bytes().to_vec(),
+            Some(text.to_string()),
+        ))
+    }
+
+    fn metrics(items: &[PipelineItem]) -> Vec<(String, f64)> {
+        let mut metrics: Vec<(String, f64)> = items
+            .iter()
+            .filter_map(|item| match item {
+                PipelineItem::Metric(metric) => Some((metric.name.clone(), metric.value)),
+                _ => None,
+            })
+            .collect();
+        metrics.sort_by(|a, b| a.0.cmp(&b.0));
+        metrics
+    }
+
+    fn event_kind(items: &[PipelineItem]) -> Option<&str> {
+        items.iter().find_map(|item| match item {
+            PipelineItem::Event(event) => Some(event.kind.as_str()),
+            _ => None,
+        })
+    }
+
+    #[test]
+    fn flattens_nested_objects_and_arrays() {
+        let stage = JsonLineStage::default();
+        let items = parse(
+            &stage,
+            r#"{"imu":{"accel":{"x":0.5,"y":-1}},"samples":[3,4],"ok":true,"name":"imu0"}"#,
+        );
+        assert_eq!(
+            metrics(&items),
+            vec![
+                ("imu.accel.x".to_string(), 0.5),
+                ("imu.accel.y".to_string(), -1.0),
+                ("samples.0".to_string(), 3.0),
+                ("samples.1".to_string(), 4.0),
+            ]
+        );
+        let PipelineItem::Event(event) = items.last().unwrap() else {
+            panic!("expected the object as an event");
+        };
+        assert_eq!(event.kind, "json");
+        assert_eq!(event.payload["name"], "imu0");
+    }
+
+    #[test]
+    fn kind_field_picks_event_kind() {
+        let stage = JsonLineStage {
+            kind_field: Some("meta.type".to_string()),
+            metrics: false,
+            ..JsonLineStage::default()
+        };
+        let items = parse(&stage, r#"{"meta":{"type":"boot"},"v":1}"#);
+        assert_eq!(items.len(), 1);
+        assert_eq!(event_kind(&items), Some("boot"));
+        // 路径不存在或不是字符串时退回配置的 kind
+        assert_eq!(
+            event_kind(&parse(&stage, r#"{"meta":{"type":3}}"#)),
+            Some("json")
+        );
+
+        let stage = JsonLineStage {
+            kind_field: Some("events.1".to_string()),
+            ..JsonLineStage::default()
+        };
+        assert_eq!(
+            event_kind(&parse(&stage, r#"{"events":["a","b"]}"#)),
+            Some("b")
+        );
+    }
+
+    #[test]
+    fn malformed_lines_pass_through() {
+        let stage = JsonLineStage::default();
+        for text in ["temp=21", "{\"open\": ", "[1, 2]", "{} trailing"] {
+            let items = parse(&stage, text);
+            assert!(
+                matches!(&items[..], [PipelineItem::Packet(packet)] if packet.text.as_deref() == Some(text)),
+                "{text}"
+            );
+        }
+    }
+}
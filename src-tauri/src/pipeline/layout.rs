@@ -0,0 +1,364 @@
+// 按 C 结构体布局把二进制帧解码为具名 Metric
+use std::collections::HashSet;
+
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::core::types::{
+    DataPacket, Metric, ParamSchema, ParamType, ParsedEvent, ParserDescriptor, PipelineItem,
+};
+use crate::pipeline::framing::Endian;
+use crate::pipeline::params::StageParams;
+use crate::pipeline::stages::Stage;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FieldType {
+    U8,
+    I8,
+    U16,
+    I16,
+    U32,
+    I32,
+    U64,
+    I64,
+    F32,
+    F64,
+}
+
+impl FieldType {
+    pub fn size(self) -> usize {
+        match self {
+            FieldType::U8 | FieldType::I8 => 1,
+            FieldType::U16 | FieldType::I16 => 2,
+            FieldType::U32 | FieldType::I32 | FieldType::F32 => 4,
+            FieldType::U64 | FieldType::I64 | FieldType::F64 => 8,
+        }
+    }
+
+    fn is_signed(self) -> bool {
+        matches!(
+            self,
+            FieldType::I8 | FieldType::I16 | FieldType::I32 | FieldType::I64
+        )
+    }
+
+    fn is_float(self) -> bool {
+        matches!(self, FieldType::F32 | FieldType::F64)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FieldEndian {
+    Le,
+    Be,
+}
+
+// 前端提交的字段描述；value = raw * scale + offset
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FieldSpec {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub field_type: FieldType,
+    // 相对帧起点的字节偏移；缺省时紧跟上一个字段（packed 布局）
+    #[serde(default)]
+    pub byte_offset: Option<usize>,
+    #[serde(default)]
+    pub endian: Option<FieldEndian>,
+    // 位域宽度；相邻同类型位域按 LSB 优先依次装入同一个存储单元，与 GCC 小端目标一致
+    #[serde(default)]
+    pub bits: Option<u32>,
+    #[serde(default)]
+    pub bit_offset: Option<u32>,
+    #[serde(default = "default_scale")]
+    pub scale: f64,
+    #[serde(default)]
+    pub offset: f64,
+}
+
+fn default_scale() -> f64 {
+    1.0
+}
+
+#[derive(Debug, Clone)]
+struct ResolvedField {
+    name: String,
+    field_type: FieldType,
+    position: usize,
+    endian: Endian,
+    // (shift, width)
+    bits: Option<(u32, u32)>,
+    scale: f64,
+    offset: f64,
+}
+
+pub struct StructDecoder {
+    fields: Vec<ResolvedField>,
+    size: usize,
+}
+
+impl StructDecoder {
+    pub fn descriptor() -> ParserDescriptor {
+        ParserDescriptor::stage(
+            "struct_decoder",
+            "Struct Decoder",
+            vec![
+                ParamSchema::new(
+                    "fields",
+                    ParamType::Json,
+                    Value::Null,
+                    "Struct fields: [{name, type, byte_offset?, endian?, bits?, bit_offset?, scale?, offset?}]",
+                ),
+                ParamSchema::new(
+                    "endian",
+                    ParamType::Enum,
+                    json!("le"),
+                    "Default byte order for fields that do not set one",
+                )
+                .options(&["le", "be"]),
+            ],
+        )
+    }
+
+    pub fn build(params: &StageParams) -> Result<Box<dyn Stage + Send + Sync>, String> {
+        let fields = params
+            .get("fields")
+            .ok_or_else(|| "parameter `fields`: required".to_string())?;
+        let fields: Vec<FieldSpec> = serde_json::from_value(fields.clone())
+            .map_err(|err| format!("parameter `fields`: {err}"))?;
+        let endian = match params.get_str("endian") {
+            Some("be") => Endian::Big,
+            _ => Endian::Little,
+        };
+        let decoder = StructDecoder::new(&fields, endian)
+            .map_err(|err| format!("parameter `fields`: {err}"))?;
+        Ok(Box::new(decoder))
+    }
+
+    pub fn new(fields: &[FieldSpec], endian: Endian) -> Result<Self, String> {
+        if fields.is_empty() {
+            return Err("at least one field is required".to_string());
+        }
+
+        let mut names = HashSet::new();
+        let mut resolved = Vec::with_capacity(fields.len());
+        let mut cursor = 0;
+        // 当前位域存储单元：(字节位置, 类型, 已用位数)
+        let mut unit: Option<(usize, FieldType, u32)> = None;
+        for field in fields {
+            let err = |message: String| format!("field `{}`: {message}", field.name);
+            if field.name.trim().is_empty() {
+                return Err("field name must not be empty".to_string());
+            }
+            if !names.insert(field.name.as_str()) {
+                return Err(err("duplicate field name".to_string()));
+            }
+            if !field.scale.is_finite() || !field.offset.is_finite() {
+                return Err(err("scale and offset must be finite".to_string()));
+            }
+
+            let size = field.field_type.size();
+            let storage_bits = size as u32 * 8;
+            let (position, bits) = match field.bits {
+                None => {
+                    if field.bit_offset.is_some() {
+                        return Err(err("bit_offset requires bits".to_string()));
+                    }
+                    unit = None;
+                    (field.byte_offset.unwrap_or(cursor), None)
+                }
+                Some(width) => {
+                    if field.field_type.is_float() {
+                        return Err(err("bitfields require an integer type".to_string()));
+                    }
+                    let shared = unit.filter(|(_, unit_type, used)| {
+                        field.byte_offset.is_none()
+                            && field.bit_offset.is_none()
+                            && *unit_type == field.field_type
+                            && used
+                                .checked_add(width)
+                                .is_some_and(|end| end <= storage_bits)
+                    });
+                    let (position, shift) = match shared {
+                        Some((position, _, used)) => (position, used),
+                        None => (
+                            field.byte_offset.unwrap_or(cursor),
+                            field.bit_offset.unwrap_or(0),
+                        ),
+                    };
+                    let end = shift
+                        .checked_add(width)
+                        .filter(|end| width > 0 && *end <= storage_bits);
+                    let Some(end) = end else {
+                        return Err(err(format!(
+                            "bits {shift}..{} do not fit in {storage_bits}-bit storage",
+                            u64::from(shift) + u64::from(width)
+                        )));
+                    };
+                    unit = Some((position, field.field_type, end));
+                    (position, Some((shift, width)))
+                }
+            };
+            let end = position
+                .checked_add(size)
+                .ok_or_else(|| err(format!("byte_offset {position} is out of range")))?;
+            cursor = cursor.max(end);
+
+            resolved.push(ResolvedField {
+                name: field.name.clone(),
+                field_type: field.field_type,
+                position,
+                endian: match field.endian {
+                    Some(FieldEndian::Le) => Endian::Little,
+                    Some(FieldEndian::Be) => Endian::Big,
+                    None => endian,
+                },
+                bits,
+                scale: field.scale,
+                offset: field.offset,
+            });
+        }
+
+        Ok(Self {
+            fields: resolved,
+            size: cursor,
+        })
+    }
+
+    fn decode(&self, packet: DataPacket) -> Vec<PipelineItem> {
+        let mut out = Vec::with_capacity(self.fields.len());
+        let mut missing = Vec::new();
+        for field in &self.fields {
+            let Some(bytes) = packet
+                .raw
+                .get(field.position..)
+                .and_then(|rest| rest.get(..field.field_type.size()))
+            else {
+                missing.push(field.name.as_str());
+                continue;
+            };
+            let value = decode_field(field, bytes) * field.scale + field.offset;
+            out.push(PipelineItem::Metric(Metric::from_packet(
+                &packet,
+                &field.name,
+                value,
+            )));
+        }
+
+        // 帧比布局短时仍输出能解出的字段，并报告缺失部分
+        if !missing.is_empty() {
+            out.push(PipelineItem::Event(ParsedEvent {
+                ts: packet.ts,
+                kind: "decode_error".to_string(),
+                payload: json!({
+                    "source_id": packet.source_id,
+                    "reason": "frame too short",
+                    "expected_length": self.size,
+                    "length": packet.raw.len(),
+                    "missing_fields": missing,
+                }),
+            }));
+        }
+        out
+    }
+}
+
+fn decode_field(field: &ResolvedField, bytes: &[u8]) -> f64 {
+    let raw = field.endian.read_uint(bytes);
+    match field.field_type {
+        FieldType::F32 => return f64::from(f32::from_bits(raw as u32)),
+        FieldType::F64 => return f64::from_bits(raw),
+        _ => {}
+    }
+
+    let (raw, width) = match field.bits {
+        Some((shift, width)) => ((raw >> shift) & mask(width), width),
+        None => (raw, field.field_type.size() as u32 * 8),
+    };
+    if field.field_type.is_signed() && width < 64 && raw & (1 << (width - 1)) != 0 {
+        // 按字段宽度做符号扩展
+        (raw as i64 - (1i64 << width)) as f64
+    } else if field.field_type.is_signed() {
+        raw as i64 as f64
+    } else {
+        raw as f64
+    }
+}
+
+fn mask(width: u32) -> u64 {
+    if width >= 64 {
+        u64::MAX
+    } else {
+        (1 << width) - 1
+    }
+}
+
+impl Stage for StructDecoder {
+    fn name(&self) -> &str {
+        "struct_decoder"
+    }
+
+    fn process(&mut self, item: PipelineItem) -> Vec<PipelineItem> {
+        match item {
+            PipelineItem::Packet(packet) => self.decode(packet),
+            other => vec![other],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fields(value: Value) -> Vec<FieldSpec> {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn decodes_packed_fields_and_bitfields() {
+        let decoder = StructDecoder::new(
+            &fields(json!([
+                { "name": "id", "type": "u16" },
+                { "name": "low", "type": "u8", "bits": 4 },
+                { "name": "high", "type": "u8", "bits": 4 },
+                { "name": "temp", "type": "i16", "endian": "be", "scale": 0.1 },
+            ])),
+            Endian::Little,
+        )
+        .unwrap();
+        let packet = DataPacket::new("s", vec![0x34, 0x12, 0xA5, 0xFF, 0x38], None);
+        let values: Vec<(String, f64)> = decoder
+            .decode(packet)
+            .into_iter()
+            .filter_map(|item| match item {
+                PipelineItem::Metric(metric) => Some((metric.name, metric.value)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(values[0], ("id".to_string(), 0x1234 as f64));
+        assert_eq!(values[1], ("low".to_string(), 5.0));
+        assert_eq!(values[2], ("high".to_string(), 10.0));
+        assert!((values[3].1 - (-20.0)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn rejects_offsets_that_overflow() {
+        let err = StructDecoder::new(
+            &fields(json!([{ "name": "x", "type": "u32", "byte_offset": usize::MAX }])),
+            Endian::Little,
+        )
+        .err()
+        .unwrap();
+        assert!(err.contains("out of range"), "{err}");
+
+        let err = StructDecoder::new(
+            &fields(json!([{ "name": "x", "type": "u8", "bits": 4, "bit_offset": u32::MAX }])),
+            Endian::Little,
+        )
+        .err()
+        .unwrap();
+        assert!(err.contains("do not fit"), "{err}");
+    }
+}
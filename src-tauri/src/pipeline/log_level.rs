@@ -0,0 +1,185 @@
+// 识别常见嵌入式日志格式，输出 kind 为 "log" 的 ParsedEvent，level 统一为 error/warn/info/debug/trace
+use regex::Regex;
+use serde_json::json;
+
+use crate::core::types::{DataPacket, ParsedEvent, ParserDescriptor, PipelineItem};
+use crate::pipeline::params::StageParams;
+use crate::pipeline::stages::Stage;
+
+// Zephyr：[00:00:01.234,000] <inf> module: msg，时间戳可能被关闭或显示为 cycle 计数
+const ZEPHYR_PATTERN: &str = r"^(?:\[(?P<ts>[\d:.,]+)\]\s*)?<(?P<level>err|wrn|inf|dbg)>\s+(?:(?P<module>[\w.\-/]+):\s?)?(?P<msg>.*)$";
+// ESP-IDF：I (1234) tag: msg，时间戳为毫秒或 hh:mm:ss.sss
+const ESP_IDF_PATTERN: &str =
+    r"^(?P<level>[EWIDV]) \((?P<ts>[\d:.]+)\) (?P<module>[^:]+): ?(?P<msg>.*)$";
+// [INFO] msg，前面可以带时间戳：12:00:01 [INFO] msg、[2024-01-01 12:00:01.123] [WARN] msg
+const BRACKET_PATTERN: &str = r"(?i)^(?:\[?(?P<ts>\d[\d:.,\-/T ]*?)\]?\s+)?\[(?P<level>trace|verbose|debug|info|warn|warning|error|fatal|critical)\]\s*(?P<msg>.*)$";
+
+pub struct LogLevelStage {
+    zephyr: Regex,
+    esp_idf: Regex,
+    bracket: Regex,
+}
+
+impl Default for LogLevelStage {
+    fn default() -> Self {
+        Self {
+            zephyr: Regex::new(ZEPHYR_PATTERN).expect("valid zephyr pattern"),
+            esp_idf: Regex::new(ESP_IDF_PATTERN).expect("valid esp-idf pattern"),
+            bracket: Regex::new(BRACKET_PATTERN).expect("valid bracket pattern"),
+        }
+    }
+}
+
+impl LogLevelStage {
+    pub fn descriptor() -> ParserDescriptor {
+        ParserDescriptor::stage("log_level", "Log Levels", Vec::new())
+    }
+
+    pub fn build(_params: &StageParams) -> Result<Box<dyn Stage + Send + Sync>, String> {
+        Ok(Box::new(LogLevelStage::default()))
+    }
+
+    fn parse(&self, packet: DataPacket) -> Vec<PipelineItem> {
+        let Some(text) = packet.text.as_deref().map(str::trim_end) else {
+            return vec![PipelineItem::Packet(packet)];
+        };
+
+        let (format, captures, device_ts) = if let Some(captures) = self.zephyr.captures(text) {
+            let ts = captures.name("ts").and_then(|ts| parse_clock(ts.as_str()));
+            ("zephyr", captures, ts)
+        } else if let Some(captures) = self.esp_idf.captures(text) {
+            let ts = captures.name("ts").and_then(|ts| {
+                let ts = ts.as_str();
+                if ts.contains(':') {
+                    parse_clock(ts)
+                } else {
+                    ts.parse::<f64>().ok().map(|millis| millis / 1000.0)
+                }
+            });
+            ("esp_idf", captures, ts)
+        } else if let Some(captures) = self.bracket.captures(text) {
+            let ts = captures.name("ts").and_then(|ts| parse_clock(ts.as_str()));
+            ("bracket", captures, ts)
+        } else {
+            return vec![PipelineItem::Packet(packet)];
+        };
+
+        let field = |name: &str| captures.name(name).map(|value| value.as_str());
+        let level = field("level").unwrap_or_default();
+        vec![PipelineItem::Event(ParsedEvent {
+            ts: packet.ts,
+            kind: "log".to_string(),
+            payload: json!({
+                "source_id": packet.source_id,
+                "format": format,
+                "level": normalize_level(level),
+                "level_raw": level,
+                "module": field("module").map(str::trim),
+                "device_ts": device_ts,
+                "device_ts_raw": field("ts"),
+                "message": field("msg").unwrap_or_default(),
+            }),
+        })]
+    }
+}
+
+impl Stage for LogLevelStage {
+    fn name(&self) -> &str {
+        "log_level"
+    }
+
+    fn process(&mut self, item: PipelineItem) -> Vec<PipelineItem> {
+        match item {
+            PipelineItem::Packet(packet) => self.parse(packet),
+            other => vec![other],
+        }
+    }
+}
+
+fn normalize_level(level: &str) -> &'static str {
+    match level.to_ascii_lowercase().as_str() {
+        "err" | "e" | "error" | "fatal" | "critical" => "error",
+        "wrn" | "w" | "warn" | "warning" => "warn",
+        "inf" | "i" | "info" => "info",
+        "dbg" | "d" | "debug" => "debug",
+        _ => "trace",
+    }
+}
+
+// hh:mm:ss.mmm,uuu 或 hh:mm:ss.mmm 转为秒；纯数字（Zephyr cycle 计数）无法换算，返回 None
+fn parse_clock(ts: &str) -> Option<f64> {
+    if !ts.contains(':') {
+        return None;
+    }
+    let (clock, micros) = match ts.split_once(',') {
+        Some((clock, micros)) => (clock, micros.parse::<f64>().ok()? / 1_000_000.0),
+        None => (ts, 0.0),
+    };
+    let seconds = clock.split(':').try_fold(0.0, |total, part| {
+        part.parse::<f64>().ok().map(|value| total * 60.0 + value)
+    })?;
+    Some(seconds + micros)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::Value;
+
+    use super::*;
+
+    fn parse(text: &str) -> Option<Value> {
+        let stage = LogLevelStage::default();
+        let packet = DataPacket::new("s", text.as_bytes().to_vec(), Some(text.to_string()));
+        match stage.parse(packet).remove(0) {
+            PipelineItem::Event(event) => Some(event.payload),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn parses_zephyr_logs() {
+        let log = parse("[00:00:01.234,500] <wrn> net_if: link down").unwrap();
+        assert_eq!(log["format"], "zephyr");
+        assert_eq!(log["level"], "warn");
+        assert_eq!(log["module"], "net_if");
+        assert_eq!(log["message"], "link down");
+        assert!((log["device_ts"].as_f64().unwrap() - 1.2345).abs() < 1e-9);
+
+        // 关闭时间戳、没有模块名
+        let log = parse("<err> boot failed").unwrap();
+        assert_eq!(log["level"], "error");
+        assert_eq!(log["module"], Value::Null);
+        assert_eq!(log["device_ts"], Value::Null);
+    }
+
+    #[test]
+    fn parses_esp_idf_logs() {
+        let log = parse("I (1234) wifi: connected").unwrap();
+        assert_eq!(log["format"], "esp_idf");
+        assert_eq!(log["level"], "info");
+        assert_eq!(log["module"], "wifi");
+        assert_eq!(log["message"], "connected");
+        assert_eq!(log["device_ts"], 1.234);
+    }
+
+    #[test]
+    fn parses_bracket_logs_with_optional_timestamp() {
+        let log = parse("[DEBUG] starting").unwrap();
+        assert_eq!(log["format"], "bracket");
+        assert_eq!(log["level"], "debug");
+        assert_eq!(log["message"], "starting");
+
+        let log = parse("12:00:01 [INFO] sensor ready").unwrap();
+        assert_eq!(log["level"], "info");
+        assert_eq!(log["message"], "sensor ready");
+        assert_eq!(log["device_ts_raw"], "12:00:01");
+        assert_eq!(log["device_ts"], 43201.0);
+
+        let log = parse("[2024-01-01 12:00:01.123] [Warning] low battery").unwrap();
+        assert_eq!(log["level"], "warn");
+        assert_eq!(log["device_ts_raw"], "2024-01-01 12:00:01.123");
+        assert_eq!(log["message"], "low battery");
+
+        assert!(parse("temp=21 [INFO] not a prefix").is_none());
+    }
+}
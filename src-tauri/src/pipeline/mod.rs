@@ -1,6 +1,18 @@
+pub mod ansi;
+pub mod capture;
+pub mod csv;
+pub mod decode;
+pub mod framing;
+pub mod json_line;
+pub mod layout;
+pub mod log_level;
+pub mod params;
 pub mod pipeline;
+pub mod spec;
 pub mod stages;
+pub mod tokenizer;
 
 pub use pipeline::Pipeline;
+pub use spec::PipelineSpec;
 #[allow(unused_imports)]
-pub use stages::{FloatExtractor, LineSplitter, Stage};
+pub use stages::{FloatExtractor, KeyValueExtractor, LineSplitter, Stage};
@@ -0,0 +1,145 @@
+use serde_json::{Map, Value};
+
+use crate::core::types::{ParamSchema, ParamType};
+
+// 已按 ParamSchema 校验并补齐默认值的 stage 参数
+#[derive(Debug, Clone, Default)]
+pub struct StageParams {
+    values: Map<String, Value>,
+}
+
+#[allow(dead_code)]
+impl StageParams {
+    pub fn resolve(schema: &[ParamSchema], params: &Value) -> Result<Self, String> {
+        let empty = Map::new();
+        let params = match params {
+            Value::Null => &empty,
+            Value::Object(params) => params,
+            _ => return Err("params must be an object".to_string()),
+        };
+
+        if let Some(name) = params
+            .keys()
+            .find(|name| !schema.iter().any(|param| &param.name == *name))
+        {
+            return Err(format!("parameter `{name}`: unknown parameter"));
+        }
+
+        let mut values = Map::new();
+        for param in schema {
+            let value = match params.get(&param.name) {
+                Some(Value::Null) | None => param.default.clone(),
+                Some(value) => {
+                    check_value(param, value)
+                        .map_err(|err| format!("parameter `{}`: {err}", param.name))?;
+                    value.clone()
+                }
+            };
+            if !value.is_null() {
+                values.insert(param.name.clone(), value);
+            }
+        }
+        Ok(Self { values })
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Value> {
+        self.values.get(name)
+    }
+
+    pub fn get_bool(&self, name: &str) -> bool {
+        self.get(name).and_then(Value::as_bool).unwrap_or(false)
+    }
+
+    pub fn get_i64(&self, name: &str) -> Option<i64> {
+        self.get(name).and_then(Value::as_i64)
+    }
+
+    pub fn get_usize(&self, name: &str) -> Option<usize> {
+        self.get(name)
+            .and_then(Value::as_u64)
+            .and_then(|value| usize::try_from(value).ok())
+    }
+
+    pub fn get_f64(&self, name: &str) -> Option<f64> {
+        self.get(name).and_then(Value::as_f64)
+    }
+
+    pub fn get_str(&self, name: &str) -> Option<&str> {
+        self.get(name).and_then(Value::as_str)
+    }
+
+    pub fn get_string_list(&self, name: &str) -> Vec<String> {
+        self.get(name)
+            .and_then(Value::as_array)
+            .map(|items| {
+                items
+                    .iter()
+                    .filter_map(|item| item.as_str().map(str::to_string))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+fn check_value(param: &ParamSchema, value: &Value) -> Result<(), String> {
+    match param.param_type {
+        ParamType::Bool => {
+            if !value.is_boolean() {
+                return Err("expected a boolean".to_string());
+            }
+        }
+        ParamType::Integer => {
+            if !(value.is_i64() || value.is_u64()) {
+                return Err("expected an integer".to_string());
+            }
+            check_range(param, value.as_f64().unwrap_or_default())?;
+        }
+        ParamType::Number => {
+            let number = value
+                .as_f64()
+                .ok_or_else(|| "expected a number".to_string())?;
+            check_range(param, number)?;
+        }
+        ParamType::String => {
+            if !value.is_string() {
+                return Err("expected a string".to_string());
+            }
+        }
+        ParamType::Enum => {
+            let option = value
+                .as_str()
+                .ok_or_else(|| "expected a string".to_string())?;
+            if !param.options.iter().any(|allowed| allowed == option) {
+                return Err(format!(
+                    "expected one of {}, got \"{option}\"",
+                    param.options.join(", ")
+                ));
+            }
+        }
+        ParamType::StringList => {
+            let valid = value
+                .as_array()
+                .map(|items| items.iter().all(Value::is_string))
+                .unwrap_or(false);
+            if !valid {
+                return Err("expected a list of strings".to_string());
+            }
+        }
+        ParamType::Json => {}
+    }
+    Ok(())
+}
+
+fn check_range(param: &ParamSchema, number: f64) -> Result<(), String> {
+    if let Some(min) = param.min {
+        if number < min {
+            return Err(format!("must be >= {min}, got {number}"));
+        }
+    }
+    if let Some(max) = param.max {
+        if number > max {
+            return Err(format!("must be <= {max}, got {number}"));
+        }
+    }
+    Ok(())
+}
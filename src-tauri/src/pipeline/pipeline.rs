@@ -1,4 +1,4 @@
-use crate::core::types::PipelineItem;
+use crate::core::types::{PipelineItem, Timestamp};
 use crate::pipeline::stages::Stage;
 
 pub struct Pipeline {
@@ -22,9 +22,24 @@ impl Pipeline {
         self.stages.push(stage);
     }
 
-    pub fn process(&self, input: PipelineItem) -> Vec<PipelineItem> {
-        let mut items = vec![input];
-        for stage in &self.stages {
+    pub fn process(&mut self, input: PipelineItem) -> Vec<PipelineItem> {
+        self.run_from(0, vec![input])
+    }
+
+    // 让有缓冲的 stage 按时间冲刷该 source 的残留数据，产出继续流经后续 stage
+    pub fn tick(&mut self, source_id: &str, now: Timestamp) -> Vec<PipelineItem> {
+        let mut out = Vec::new();
+        for index in 0..self.stages.len() {
+            let flushed = self.stages[index].tick(source_id, now);
+            if !flushed.is_empty() {
+                out.extend(self.run_from(index + 1, flushed));
+            }
+        }
+        out
+    }
+
+    fn run_from(&mut self, start: usize, mut items: Vec<PipelineItem>) -> Vec<PipelineItem> {
+        for stage in &mut self.stages[start..] {
             let mut next = Vec::new();
             for item in items {
                 next.extend(stage.process(item));
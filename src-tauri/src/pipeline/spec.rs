@@ -0,0 +1,16 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PipelineSpec {
+    pub id: String,
+    #[serde(default)]
+    pub stages: Vec<StageSpec>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StageSpec {
+    // ParserRegistry 中的 stage id，例如 "line_splitter"
+    pub id: String,
+    #[serde(default)]
+    pub params: serde_json::Value,
+}
@@ -1,39 +1,455 @@
-use crate::core::types::{now_millis, DataPacket, Metric, PipelineItem};
+use std::collections::HashMap;
+
+use serde_json::{json, Value};
+
+use crate::core::types::{
+    DataPacket, Metric, ParamSchema, ParamType, ParsedEvent, ParserDescriptor, PipelineItem,
+    Timestamp,
+};
+use crate::pipeline::framing::Endian;
+use crate::pipeline::params::StageParams;
+use crate::pipeline::tokenizer::{scan_numbers, ScanOptions};
 
 #[allow(dead_code)]
 pub trait Stage {
     fn name(&self) -> &str;
-    fn process(&self, item: PipelineItem) -> Vec<PipelineItem>;
+    fn process(&mut self, item: PipelineItem) -> Vec<PipelineItem>;
+
+    // 由分发线程定期调用；有跨包缓冲的 stage 在这里把超时的残留数据吐出来
+    fn tick(&mut self, _source_id: &str, _now: Timestamp) -> Vec<PipelineItem> {
+        Vec::new()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineTerminator {
+    // \n，顺带去掉行尾的 \r，因此也兼容 \r\n
+    Lf,
+    CrLf,
+    Cr,
+    Byte(u8),
+}
+
+pub const DEFAULT_MAX_LINE_LENGTH: usize = 4096;
+
+// 未凑满一行的数据；同一个管线可能被多个 source 共用，TCP server 下还有多个 peer，
+// 因此按 (source_id, tags) 分别缓冲
+struct PendingLine {
+    // 这一行对应的原始字节
+    raw: Vec<u8>,
+    // 上游已解码时按 text 切分，raw 跟随；None 表示按 raw 切分，输出时再按 UTF-8 转成 text
+    text: Option<String>,
+    // 行首字节所在包的时间戳
+    ts: Timestamp,
+    // 最近一次收到数据的时间，用于空闲超时
+    last_ts: Timestamp,
+}
+
+impl PendingLine {
+    fn new(ts: Timestamp, decoded: bool) -> Self {
+        Self {
+            raw: Vec::new(),
+            text: decoded.then(String::new),
+            ts,
+            last_ts: ts,
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.raw.is_empty() && self.text.as_ref().is_none_or(String::is_empty)
+    }
+}
+
+pub struct LineSplitter {
+    pub skip_empty: bool,
+    pub terminator: LineTerminator,
+    pub max_line_length: usize,
+    // 0 表示不做空闲冲刷
+    pub idle_timeout_ms: u64,
+    pending: HashMap<(String, Vec<String>), PendingLine>,
+}
+
+impl Default for LineSplitter {
+    fn default() -> Self {
+        Self::new(LineTerminator::Lf)
+    }
+}
+
+impl LineSplitter {
+    pub fn descriptor() -> ParserDescriptor {
+        ParserDescriptor::stage(
+            "line_splitter",
+            "Line Splitter",
+            vec![
+                ParamSchema::new(
+                    "skip_empty",
+                    ParamType::Bool,
+                    json!(true),
+                    "Drop empty lines instead of emitting empty packets",
+                ),
+                ParamSchema::new(
+                    "terminator",
+                    ParamType::Enum,
+                    json!("lf"),
+                    "Line terminator; `lf` also strips a trailing \\r, `byte` uses terminator_byte",
+                )
+                .options(&["lf", "crlf", "cr", "byte"]),
+                ParamSchema::new(
+                    "terminator_byte",
+                    ParamType::Integer,
+                    json!(0),
+                    "Custom terminator byte used when terminator is `byte`",
+                )
+                .range(0.0, 255.0),
+                ParamSchema::new(
+                    "max_line_length",
+                    ParamType::Integer,
+                    json!(DEFAULT_MAX_LINE_LENGTH),
+                    "Emit a line once this many bytes are buffered without a terminator",
+                )
+                .range(1.0, 1_048_576.0),
+                ParamSchema::new(
+                    "idle_timeout_ms",
+                    ParamType::Integer,
+                    json!(0),
+                    "Flush a partial line after this long without new data; 0 disables",
+                )
+                .range(0.0, 60_000.0),
+            ],
+        )
+    }
+
+    pub fn build(params: &StageParams) -> Result<Box<dyn Stage + Send + Sync>, String> {
+        let terminator = match params.get_str("terminator") {
+            Some("crlf") => LineTerminator::CrLf,
+            Some("cr") => LineTerminator::Cr,
+            Some("byte") => {
+                LineTerminator::Byte(params.get_i64("terminator_byte").unwrap_or_default() as u8)
+            }
+            _ => LineTerminator::Lf,
+        };
+        let mut splitter = LineSplitter::new(terminator);
+        splitter.skip_empty = params.get_bool("skip_empty");
+        splitter.max_line_length = params
+            .get_usize("max_line_length")
+            .unwrap_or(DEFAULT_MAX_LINE_LENGTH);
+        splitter.idle_timeout_ms = params.get_i64("idle_timeout_ms").unwrap_or_default() as u64;
+        Ok(Box::new(splitter))
+    }
+
+    pub fn new(terminator: LineTerminator) -> Self {
+        Self {
+            skip_empty: true,
+            terminator,
+            max_line_length: DEFAULT_MAX_LINE_LENGTH,
+            idle_timeout_ms: 0,
+            pending: HashMap::new(),
+        }
+    }
+
+    fn is_idle(&self, pending: &PendingLine, now: Timestamp) -> bool {
+        self.idle_timeout_ms > 0
+            && now.mono_micros.saturating_sub(pending.last_ts.mono_micros)
+                >= self.idle_timeout_ms * 1000
+    }
+
+    // 分隔字节；CrLf 时为 \n
+    fn separator(&self) -> u8 {
+        match self.terminator {
+            LineTerminator::Lf | LineTerminator::CrLf => b'\n',
+            LineTerminator::Cr => b'\r',
+            LineTerminator::Byte(terminator) => terminator,
+        }
+    }
+
+    // \r 落在包尾时先留在缓冲里，等下一个包决定
+    fn is_terminator(&self, current: u8, after_cr: bool) -> bool {
+        current == self.separator() && (self.terminator != LineTerminator::CrLf || after_cr)
+    }
+
+    fn split(&mut self, packet: DataPacket) -> Vec<DataPacket> {
+        let key = (packet.source_id.clone(), packet.tags.clone());
+        let mut out = Vec::new();
+        let decoded = packet.text.is_some();
+
+        let mut line = match self.pending.remove(&key) {
+            // 上一段残留已经空闲超时，或切分方式与本包不同，单独成行，不和新数据拼在一起
+            Some(pending)
+                if self.is_idle(&pending, packet.ts) || pending.text.is_some() != decoded =>
+            {
+                out.extend(self.line_packet(&key, pending));
+                PendingLine::new(packet.ts, decoded)
+            }
+            Some(pending) => pending,
+            None => PendingLine::new(packet.ts, decoded),
+        };
+        match &packet.text {
+            Some(text) => self.split_text(&key, text, &packet, &mut line, &mut out),
+            None => self.split_raw(&key, &packet, &mut line, &mut out),
+        }
+
+        if !line.is_empty() {
+            line.last_ts = packet.ts;
+            self.pending.insert(key, line);
+        }
+        out
+    }
+
+    fn split_raw(
+        &self,
+        key: &(String, Vec<String>),
+        packet: &DataPacket,
+        line: &mut PendingLine,
+        out: &mut Vec<DataPacket>,
+    ) {
+        for &byte in &packet.raw {
+            let terminated = self.is_terminator(byte, line.raw.last() == Some(&b'\r'));
+            line.raw.push(byte);
+            if terminated {
+                self.finish_line(key, line, true, packet.ts, out);
+            } else if line.raw.len() >= self.max_line_length {
+                // 超长行按上限截断输出，剩余部分作为新的一行继续累积；不切开末尾的 UTF-8 多字节字符
+                let tail = line.raw.split_off(utf8_boundary(&line.raw));
+                self.finish_line(key, line, false, packet.ts, out);
+                line.raw = tail;
+            }
+        }
+    }
+
+    // 已解码的包按 text 切分。未经转换的 UTF-8 包里字符与字节一一对应；
+    // 其它编码只在分隔字节处对齐 raw（各编码下分隔字节都不会出现在多字节字符中），其余字节在包尾补上
+    fn split_text(
+        &self,
+        key: &(String, Vec<String>),
+        text: &str,
+        packet: &DataPacket,
+        line: &mut PendingLine,
+        out: &mut Vec<DataPacket>,
+    ) {
+        let aligned = text.as_bytes() == packet.raw.as_slice();
+        let separator = self.separator();
+        let mut raw_pos = 0;
+        for ch in text.chars() {
+            let line_text = line.text.get_or_insert_with(String::new);
+            let terminated = u8::try_from(ch)
+                .is_ok_and(|current| self.is_terminator(current, line_text.ends_with('\r')));
+            // 超长行在字符边界截断，text 不超过 max_line_length 字节
+            if !terminated
+                && !line_text.is_empty()
+                && line_text.len() + ch.len_utf8() > self.max_line_length
+            {
+                self.finish_line(key, line, false, packet.ts, out);
+            }
+
+            let raw_end = if aligned {
+                raw_pos + ch.len_utf8()
+            } else if u8::try_from(ch) == Ok(separator) {
+                packet.raw[raw_pos..]
+                    .iter()
+                    .position(|&byte| byte == separator)
+                    .map_or(packet.raw.len(), |index| raw_pos + index + 1)
+            } else {
+                raw_pos
+            };
+            line.raw.extend_from_slice(&packet.raw[raw_pos..raw_end]);
+            raw_pos = raw_end;
+
+            let line_text = line.text.get_or_insert_with(String::new);
+            line_text.push(ch);
+            if terminated {
+                self.finish_line(key, line, true, packet.ts, out);
+            } else if line_text.len() >= self.max_line_length {
+                self.finish_line(key, line, false, packet.ts, out);
+            }
+        }
+        line.raw.extend_from_slice(&packet.raw[raw_pos..]);
+    }
+
+    // 输出当前行并以 next_ts 开始新的一行；terminated 时去掉结尾的分隔符，lf/crlf 下连同前面的 \r
+    fn finish_line(
+        &self,
+        key: &(String, Vec<String>),
+        line: &mut PendingLine,
+        terminated: bool,
+        next_ts: Timestamp,
+        out: &mut Vec<DataPacket>,
+    ) {
+        let decoded = line.text.is_some();
+        let mut done = std::mem::replace(line, PendingLine::new(next_ts, decoded));
+        if terminated {
+            let separator = self.separator();
+            let strip_cr = matches!(self.terminator, LineTerminator::Lf | LineTerminator::CrLf);
+            if done.raw.last() == Some(&separator) {
+                done.raw.pop();
+                if strip_cr && done.raw.last() == Some(&b'\r') {
+                    done.raw.pop();
+                }
+            }
+            if let Some(text) = &mut done.text {
+                if text.ends_with(char::from(separator)) {
+                    text.pop();
+                    if strip_cr && text.ends_with('\r') {
+                        text.pop();
+                    }
+                }
+            }
+        }
+        out.extend(self.line_packet(key, done));
+    }
+
+    fn line_packet(
+        &self,
+        (source_id, tags): &(String, Vec<String>),
+        line: PendingLine,
+    ) -> Option<DataPacket> {
+        let text = match line.text {
+            Some(text) => text,
+            None => String::from_utf8_lossy(&line.raw).into_owned(),
+        };
+        if self.skip_empty && text.is_empty() {
+            return None;
+        }
+        Some(DataPacket {
+            ts: line.ts,
+            source_id: source_id.clone(),
+            raw: line.raw,
+            text: Some(text),
+            tags: tags.clone(),
+            spans: Vec::new(),
+        })
+    }
 }
 
-pub struct LineSplitter;
+// 末尾不完整的 UTF-8 多字节字符之前的位置；整行都不是 UTF-8 字符前缀时返回全长
+fn utf8_boundary(bytes: &[u8]) -> usize {
+    for back in 1..=bytes.len().min(3) {
+        let index = bytes.len() - back;
+        let byte = bytes[index];
+        if byte & 0xC0 == 0x80 {
+            continue;
+        }
+        let width = match byte {
+            0xC0..=0xDF => 2,
+            0xE0..=0xEF => 3,
+            0xF0..=0xF7 => 4,
+            _ => 1,
+        };
+        return if width > back && index > 0 {
+            index
+        } else {
+            bytes.len()
+        };
+    }
+    bytes.len()
+}
 
 impl Stage for LineSplitter {
     fn name(&self) -> &str {
         "line_splitter"
     }
 
-    fn process(&self, item: PipelineItem) -> Vec<PipelineItem> {
+    fn process(&mut self, item: PipelineItem) -> Vec<PipelineItem> {
         match item {
-            PipelineItem::Packet(packet) => split_lines(packet)
+            PipelineItem::Packet(packet) => self
+                .split(packet)
                 .into_iter()
                 .map(PipelineItem::Packet)
                 .collect(),
             other => vec![other],
         }
     }
+
+    fn tick(&mut self, source_id: &str, now: Timestamp) -> Vec<PipelineItem> {
+        let expired: Vec<_> = self
+            .pending
+            .iter()
+            .filter(|((id, _), pending)| id == source_id && self.is_idle(pending, now))
+            .map(|(key, _)| key.clone())
+            .collect();
+
+        expired
+            .into_iter()
+            .filter_map(|key| {
+                let pending = self.pending.remove(&key)?;
+                self.line_packet(&key, pending)
+            })
+            .map(PipelineItem::Packet)
+            .collect()
+    }
 }
 
-pub struct FloatExtractor;
+pub struct FloatExtractor {
+    pub metric_name: String,
+    pub scan: ScanOptions,
+}
+
+impl Default for FloatExtractor {
+    fn default() -> Self {
+        Self {
+            metric_name: "float".to_string(),
+            scan: ScanOptions::default(),
+        }
+    }
+}
+
+impl FloatExtractor {
+    pub fn descriptor() -> ParserDescriptor {
+        ParserDescriptor::stage(
+            "float_extractor",
+            "Float Extractor",
+            vec![
+                ParamSchema::new(
+                    "metric_name",
+                    ParamType::String,
+                    json!("float"),
+                    "Name given to every extracted metric",
+                ),
+                ParamSchema::new(
+                    "units",
+                    ParamType::Bool,
+                    json!(true),
+                    "Accept unit suffixes such as 3.3V or 10ms",
+                ),
+                ParamSchema::new(
+                    "radix_prefixes",
+                    ParamType::Bool,
+                    json!(true),
+                    "Parse 0x/0b/0o prefixed integers",
+                ),
+                ParamSchema::new(
+                    "special_values",
+                    ParamType::Bool,
+                    json!(true),
+                    "Recognize nan and inf",
+                ),
+            ],
+        )
+    }
+
+    pub fn build(params: &StageParams) -> Result<Box<dyn Stage + Send + Sync>, String> {
+        let metric_name = params.get_str("metric_name").unwrap_or_default();
+        if metric_name.is_empty() {
+            return Err("parameter `metric_name`: must not be empty".to_string());
+        }
+        Ok(Box::new(FloatExtractor {
+            metric_name: metric_name.to_string(),
+            scan: ScanOptions {
+                units: params.get_bool("units"),
+                radix_prefixes: params.get_bool("radix_prefixes"),
+                special_values: params.get_bool("special_values"),
+            },
+        }))
+    }
+}
 
 impl Stage for FloatExtractor {
     fn name(&self) -> &str {
         "float_extractor"
     }
 
-    fn process(&self, item: PipelineItem) -> Vec<PipelineItem> {
+    fn process(&mut self, item: PipelineItem) -> Vec<PipelineItem> {
         match item {
-            PipelineItem::Packet(packet) => extract_floats(packet)
+            PipelineItem::Packet(packet) => extract_floats(packet, &self.metric_name, self.scan)
                 .into_iter()
                 .map(PipelineItem::Metric)
                 .collect(),
@@ -42,56 +458,646 @@ impl Stage for FloatExtractor {
     }
 }
 
-fn split_lines(packet: DataPacket) -> Vec<DataPacket> {
-    let text = match packet.text.clone() {
-        Some(text) => text,
-        None => return vec![packet],
-    };
+pub struct KeyValueExtractor {
+    pub allow_space: bool,
+    pub field_prefix: String,
+    pub scan: ScanOptions,
+}
 
-    let mut out = Vec::new();
-    for line in text.lines() {
-        if line.is_empty() {
-            continue;
+impl Default for KeyValueExtractor {
+    fn default() -> Self {
+        Self {
+            allow_space: true,
+            field_prefix: "field".to_string(),
+            scan: ScanOptions::default(),
         }
-        out.push(DataPacket {
-            ts_millis: packet.ts_millis,
-            source_id: packet.source_id.clone(),
-            raw: line.as_bytes().to_vec(),
-            text: Some(line.to_string()),
-            tags: packet.tags.clone(),
-        });
     }
-    if out.is_empty() {
-        vec![packet]
-    } else {
-        out
+}
+
+impl KeyValueExtractor {
+    pub fn descriptor() -> ParserDescriptor {
+        ParserDescriptor::stage(
+            "key_value",
+            "Key/Value Extractor",
+            vec![
+                ParamSchema::new(
+                    "allow_space",
+                    ParamType::Bool,
+                    json!(true),
+                    "Also accept `key value` pairs separated only by whitespace",
+                ),
+                ParamSchema::new(
+                    "field_prefix",
+                    ParamType::String,
+                    json!("field"),
+                    "Prefix for positional names of values without a key",
+                ),
+            ],
+        )
+    }
+
+    pub fn build(params: &StageParams) -> Result<Box<dyn Stage + Send + Sync>, String> {
+        let field_prefix = params.get_str("field_prefix").unwrap_or_default();
+        if field_prefix.is_empty() {
+            return Err("parameter `field_prefix`: must not be empty".to_string());
+        }
+        Ok(Box::new(KeyValueExtractor {
+            allow_space: params.get_bool("allow_space"),
+            field_prefix: field_prefix.to_string(),
+            scan: ScanOptions::default(),
+        }))
     }
 }
 
-fn extract_floats(packet: DataPacket) -> Vec<Metric> {
-    let text = match packet.text {
+impl Stage for KeyValueExtractor {
+    fn name(&self) -> &str {
+        "key_value"
+    }
+
+    fn process(&mut self, item: PipelineItem) -> Vec<PipelineItem> {
+        match item {
+            PipelineItem::Packet(packet) => extract_key_values(packet, self)
+                .into_iter()
+                .map(PipelineItem::Metric)
+                .collect(),
+            other => vec![other],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChecksumKind {
+    // 多项式 0x07，初值 0
+    Crc8,
+    // CRC-16/CCITT-FALSE：多项式 0x1021，初值 0xFFFF
+    Crc16Ccitt,
+    // 多项式 0xA001（反射），初值 0xFFFF
+    Crc16Modbus,
+    // IEEE 802.3
+    Crc32,
+    Sum8,
+    Xor8,
+}
+
+impl ChecksumKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            ChecksumKind::Crc8 => "crc8",
+            ChecksumKind::Crc16Ccitt => "crc16_ccitt",
+            ChecksumKind::Crc16Modbus => "crc16_modbus",
+            ChecksumKind::Crc32 => "crc32",
+            ChecksumKind::Sum8 => "sum8",
+            ChecksumKind::Xor8 => "xor8",
+        }
+    }
+
+    pub fn width(self) -> usize {
+        match self {
+            ChecksumKind::Crc8 | ChecksumKind::Sum8 | ChecksumKind::Xor8 => 1,
+            ChecksumKind::Crc16Ccitt | ChecksumKind::Crc16Modbus => 2,
+            ChecksumKind::Crc32 => 4,
+        }
+    }
+
+    // 各算法在线路上的惯用字节序
+    pub fn default_endian(self) -> Endian {
+        match self {
+            ChecksumKind::Crc16Ccitt => Endian::Big,
+            _ => Endian::Little,
+        }
+    }
+
+    pub fn compute(self, data: &[u8]) -> u32 {
+        match self {
+            ChecksumKind::Crc8 => u32::from(data.iter().fold(0u8, |mut crc, byte| {
+                crc ^= byte;
+                for _ in 0..8 {
+                    crc = if crc & 0x80 != 0 {
+                        (crc << 1) ^ 0x07
+                    } else {
+                        crc << 1
+                    };
+                }
+                crc
+            })),
+            ChecksumKind::Crc16Ccitt => u32::from(data.iter().fold(0xFFFFu16, |mut crc, byte| {
+                crc ^= u16::from(*byte) << 8;
+                for _ in 0..8 {
+                    crc = if crc & 0x8000 != 0 {
+                        (crc << 1) ^ 0x1021
+                    } else {
+                        crc << 1
+                    };
+                }
+                crc
+            })),
+            ChecksumKind::Crc16Modbus => u32::from(data.iter().fold(0xFFFFu16, |mut crc, byte| {
+                crc ^= u16::from(*byte);
+                for _ in 0..8 {
+                    crc = if crc & 1 != 0 {
+                        (crc >> 1) ^ 0xA001
+                    } else {
+                        crc >> 1
+                    };
+                }
+                crc
+            })),
+            ChecksumKind::Crc32 => !data.iter().fold(0xFFFF_FFFFu32, |mut crc, byte| {
+                crc ^= u32::from(*byte);
+                for _ in 0..8 {
+                    crc = if crc & 1 != 0 {
+                        (crc >> 1) ^ 0xEDB8_8320
+                    } else {
+                        crc >> 1
+                    };
+                }
+                crc
+            }),
+            ChecksumKind::Sum8 => {
+                u32::from(data.iter().fold(0u8, |sum, byte| sum.wrapping_add(*byte)))
+            }
+            ChecksumKind::Xor8 => u32::from(data.iter().fold(0u8, |acc, byte| acc ^ byte)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChecksumErrorMode {
+    // 坏帧加上 crc_error 标签后照常下发
+    Tag,
+    // 坏帧替换为 kind 为 crc_error 的 ParsedEvent
+    Event,
+}
+
+pub struct ChecksumStage {
+    pub kind: ChecksumKind,
+    pub endian: Endian,
+    // 校验覆盖范围从这里开始，到校验值之前为止，用于跳过帧头/起始符
+    pub covered_from: usize,
+    // 校验值在帧内的偏移；None 表示位于帧尾
+    pub offset: Option<usize>,
+    // 校验值位于帧头时，其后的字节也计入覆盖范围；否则校验值之后的字节不参与校验
+    pub include_trailing: bool,
+    pub strip: bool,
+    pub on_error: ChecksumErrorMode,
+}
+
+impl ChecksumStage {
+    pub fn descriptor() -> ParserDescriptor {
+        ParserDescriptor::stage(
+            "checksum",
+            "Checksum / CRC",
+            vec![
+                ParamSchema::new(
+                    "algorithm",
+                    ParamType::Enum,
+                    json!("crc16_modbus"),
+                    "Checksum algorithm",
+                )
+                .options(&["crc8", "crc16_ccitt", "crc16_modbus", "crc32", "sum8", "xor8"]),
+                ParamSchema::new(
+                    "endian",
+                    ParamType::Enum,
+                    json!("auto"),
+                    "Byte order of the checksum; `auto` uses the algorithm's usual order",
+                )
+                .options(&["auto", "le", "be"]),
+                ParamSchema::new(
+                    "covered_from",
+                    ParamType::Integer,
+                    json!(0),
+                    "First byte covered by the checksum, to skip a header or start byte",
+                )
+                .range(0.0, 65_535.0),
+                ParamSchema::new(
+                    "offset",
+                    ParamType::Integer,
+                    Value::Null,
+                    "Byte offset of the checksum in the frame; unset means the frame end. Bytes after it are not covered unless include_trailing is set",
+                )
+                .range(0.0, 65_535.0),
+                ParamSchema::new(
+                    "include_trailing",
+                    ParamType::Bool,
+                    json!(false),
+                    "Also cover the bytes after the checksum, for a checksum stored in the frame header",
+                ),
+                ParamSchema::new(
+                    "strip",
+                    ParamType::Bool,
+                    json!(false),
+                    "Remove the checksum bytes from frames that pass; text that does not mirror the raw bytes is dropped",
+                ),
+                ParamSchema::new(
+                    "on_error",
+                    ParamType::Enum,
+                    json!("tag"),
+                    "`tag` marks bad frames with crc_error, `event` replaces them with a crc_error event",
+                )
+                .options(&["tag", "event"]),
+            ],
+        )
+    }
+
+    pub fn build(params: &StageParams) -> Result<Box<dyn Stage + Send + Sync>, String> {
+        let kind = match params.get_str("algorithm") {
+            Some("crc8") => ChecksumKind::Crc8,
+            Some("crc16_ccitt") => ChecksumKind::Crc16Ccitt,
+            Some("crc32") => ChecksumKind::Crc32,
+            Some("sum8") => ChecksumKind::Sum8,
+            Some("xor8") => ChecksumKind::Xor8,
+            _ => ChecksumKind::Crc16Modbus,
+        };
+        let mut stage = ChecksumStage::new(kind);
+        match params.get_str("endian") {
+            Some("le") => stage.endian = Endian::Little,
+            Some("be") => stage.endian = Endian::Big,
+            _ => {}
+        }
+        stage.covered_from = params.get_usize("covered_from").unwrap_or_default();
+        stage.offset = params.get_usize("offset");
+        if stage
+            .offset
+            .is_some_and(|offset| stage.covered_from >= offset)
+        {
+            return Err("parameter `covered_from`: must be less than `offset`".to_string());
+        }
+        stage.include_trailing = params.get_bool("include_trailing");
+        stage.strip = params.get_bool("strip");
+        stage.on_error = match params.get_str("on_error") {
+            Some("event") => ChecksumErrorMode::Event,
+            _ => ChecksumErrorMode::Tag,
+        };
+        Ok(Box::new(stage))
+    }
+
+    pub fn new(kind: ChecksumKind) -> Self {
+        Self {
+            kind,
+            endian: kind.default_endian(),
+            covered_from: 0,
+            offset: None,
+            include_trailing: false,
+            strip: false,
+            on_error: ChecksumErrorMode::Tag,
+        }
+    }
+
+    fn check(&self, mut packet: DataPacket) -> PipelineItem {
+        let width = self.kind.width();
+        let position = self
+            .offset
+            .or_else(|| packet.raw.len().checked_sub(width))
+            .filter(|position| {
+                *position >= self.covered_from && position + width <= packet.raw.len()
+            });
+        let Some(position) = position else {
+            return self.reject(packet, "frame too short", None, None);
+        };
+
+        let expected = self
+            .endian
+            .read_uint(&packet.raw[position..position + width]) as u32;
+        let covered = &packet.raw[self.covered_from..position];
+        let actual = if self.include_trailing {
+            self.kind
+                .compute(&[covered, &packet.raw[position + width..]].concat())
+        } else {
+            self.kind.compute(covered)
+        };
+        if expected != actual {
+            return self.reject(packet, "checksum mismatch", Some(expected), Some(actual));
+        }
+
+        if self.strip {
+            // text 与 raw 逐字节一致时同步删掉校验字节；按 GBK、Latin-1 等解码的 text 无法对应，直接丢弃
+            let text = packet.text.take().filter(|text| {
+                text.as_bytes() == packet.raw
+                    && text.is_char_boundary(position)
+                    && text.is_char_boundary(position + width)
+            });
+            packet.raw.drain(position..position + width);
+            packet.text = text.map(|mut text| {
+                text.replace_range(position..position + width, "");
+                text
+            });
+        }
+        PipelineItem::Packet(packet)
+    }
+
+    fn reject(
+        &self,
+        mut packet: DataPacket,
+        reason: &str,
+        expected: Option<u32>,
+        actual: Option<u32>,
+    ) -> PipelineItem {
+        match self.on_error {
+            ChecksumErrorMode::Tag => {
+                packet.tags.push("crc_error".to_string());
+                PipelineItem::Packet(packet)
+            }
+            ChecksumErrorMode::Event => PipelineItem::Event(ParsedEvent {
+                ts: packet.ts,
+                kind: "crc_error".to_string(),
+                payload: json!({
+                    "source_id": packet.source_id,
+                    "algorithm": self.kind.name(),
+                    "reason": reason,
+                    "expected": expected,
+                    "actual": actual,
+                    "raw": packet.raw,
+                    "tags": packet.tags,
+                }),
+            }),
+        }
+    }
+}
+
+impl Stage for ChecksumStage {
+    fn name(&self) -> &str {
+        "checksum"
+    }
+
+    fn process(&mut self, item: PipelineItem) -> Vec<PipelineItem> {
+        match item {
+            PipelineItem::Packet(packet) => vec![self.check(packet)],
+            other => vec![other],
+        }
+    }
+}
+
+fn extract_floats(packet: DataPacket, metric_name: &str, scan: ScanOptions) -> Vec<Metric> {
+    let text = match &packet.text {
         Some(text) => text,
         None => return Vec::new(),
     };
 
-    // TODO: Replace with a robust parser that can handle scientific notation,
-    // signed numbers, and mixed tokens without splitting.
-    let sanitized = text
-        .replace(',', " ")
-        .replace(';', " ")
-        .replace('\t', " ")
-        .replace('|', " ");
-
-    let mut metrics = Vec::new();
-    for token in sanitized.split_whitespace() {
-        if let Ok(value) = token.parse::<f64>() {
-            metrics.push(Metric {
-                ts_millis: now_millis(),
-                source_id: packet.source_id.clone(),
-                name: "float".to_string(),
-                value,
-            });
+    scan_numbers(text, scan)
+        .into_iter()
+        .map(|token| Metric::from_packet(&packet, metric_name, token.value))
+        .collect()
+}
+
+fn extract_key_values(packet: DataPacket, options: &KeyValueExtractor) -> Vec<Metric> {
+    let text = match &packet.text {
+        Some(text) => text,
+        None => return Vec::new(),
+    };
+
+    let chars: Vec<char> = text.chars().collect();
+    let mut bare_index = 0;
+    scan_numbers(text, options.scan)
+        .into_iter()
+        .map(|token| {
+            let name = match key_before(&chars, token.start, options.allow_space) {
+                Some(key) => key,
+                None => {
+                    let name = format!("{}{bare_index}", options.field_prefix);
+                    bare_index += 1;
+                    name
+                }
+            };
+            Metric::from_packet(&packet, name, token.value)
+        })
+        .collect()
+}
+
+// 从数值起点向前找 key：支持 key=value、key: value 以及 key value 三种写法
+fn key_before(chars: &[char], value_start: usize, allow_space: bool) -> Option<String> {
+    let mut i = value_start;
+    let skip_spaces = |mut i: usize| {
+        while i > 0 && (chars[i - 1] == ' ' || chars[i - 1] == '\t') {
+            i -= 1;
         }
+        i
+    };
+
+    i = skip_spaces(i);
+    let has_separator = i > 0 && matches!(chars[i - 1], '=' | ':');
+    if has_separator {
+        i = skip_spaces(i - 1);
+    } else if !allow_space || i == value_start {
+        return None;
+    }
+
+    let key_end = i;
+    while i > 0 && (chars[i - 1].is_alphanumeric() || matches!(chars[i - 1], '_' | '.')) {
+        i -= 1;
+    }
+    let key: String = chars[i..key_end].iter().collect();
+    let first = key.chars().next()?;
+    if first.is_alphabetic() || first == '_' {
+        Some(key)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(splitter: &mut LineSplitter, packet: DataPacket) -> Vec<(Vec<u8>, String)> {
+        splitter
+            .split(packet)
+            .into_iter()
+            .map(|line| (line.raw, line.text.unwrap_or_default()))
+            .collect()
+    }
+
+    fn line(raw: &[u8], text: &str) -> (Vec<u8>, String) {
+        (raw.to_vec(), text.to_string())
+    }
+
+    #[test]
+    fn raw_lines_join_across_packets() {
+        let mut splitter = LineSplitter::default();
+        assert!(lines(&mut splitter, DataPacket::new("s", b"ab\r".to_vec(), None)).is_empty());
+        assert_eq!(
+            lines(
+                &mut splitter,
+                DataPacket::new("s", b"\ncd\nef".to_vec(), None)
+            ),
+            vec![line(b"ab", "ab"), line(b"cd", "cd")]
+        );
+    }
+
+    #[test]
+    fn decoded_lines_keep_original_bytes() {
+        let mut splitter = LineSplitter::default();
+        // "你好\r\n世界\n" 的 GBK 编码
+        let raw = vec![
+            0xC4, 0xE3, 0xBA, 0xC3, b'\r', b'\n', 0xCA, 0xC0, 0xBD, 0xE7, b'\n',
+        ];
+        let packet = DataPacket::new("s", raw, Some("你好\r\n世界\n".to_string()));
+        assert_eq!(
+            lines(&mut splitter, packet),
+            vec![
+                line(&[0xC4, 0xE3, 0xBA, 0xC3], "你好"),
+                line(&[0xCA, 0xC0, 0xBD, 0xE7], "世界"),
+            ]
+        );
+    }
+
+    #[test]
+    fn long_text_lines_are_cut_on_char_boundaries() {
+        let mut splitter = LineSplitter {
+            max_line_length: 4,
+            ..LineSplitter::default()
+        };
+        let text = "aé€b\n";
+        let packet = DataPacket::new("s", text.as_bytes().to_vec(), Some(text.to_string()));
+        assert_eq!(
+            lines(&mut splitter, packet),
+            vec![line("aé".as_bytes(), "aé"), line("€b".as_bytes(), "€b"),]
+        );
+    }
+
+    #[test]
+    fn long_raw_lines_keep_multibyte_chars_whole() {
+        let mut splitter = LineSplitter {
+            max_line_length: 4,
+            ..LineSplitter::default()
+        };
+        let raw = "ab€\n".as_bytes().to_vec();
+        assert_eq!(
+            lines(&mut splitter, DataPacket::new("s", raw, None)),
+            vec![line(b"ab", "ab"), line("€".as_bytes(), "€")]
+        );
+    }
+
+    fn key_values(stage: &KeyValueExtractor, text: &str) -> Vec<(String, f64)> {
+        let packet = DataPacket::new("s", text.as_bytes().to_vec(), Some(text.to_string()));
+        extract_key_values(packet, stage)
+            .into_iter()
+            .map(|metric| (metric.name, metric.value))
+            .collect()
+    }
+
+    fn pairs(expected: &[(&str, f64)]) -> Vec<(String, f64)> {
+        expected
+            .iter()
+            .map(|(name, value)| (name.to_string(), *value))
+            .collect()
+    }
+
+    #[test]
+    fn key_values_accept_all_separators() {
+        let stage = KeyValueExtractor::default();
+        assert_eq!(
+            key_values(&stage, "temp=23.5 hum: 40 volt 3.3 imu.accel.x = -0.5"),
+            pairs(&[
+                ("temp", 23.5),
+                ("hum", 40.0),
+                ("volt", 3.3),
+                ("imu.accel.x", -0.5)
+            ])
+        );
+        assert_eq!(
+            key_values(&stage, "v=-3.3V i=12mA t=1e-3s"),
+            pairs(&[("v", -3.3), ("i", 12.0), ("t", 1e-3)])
+        );
+    }
+
+    #[test]
+    fn values_without_key_get_positional_names() {
+        let stage = KeyValueExtractor::default();
+        assert_eq!(
+            key_values(&stage, "ADC 12 34, 56"),
+            pairs(&[("ADC", 12.0), ("field0", 34.0), ("field1", 56.0)])
+        );
+
+        let stage = KeyValueExtractor {
+            allow_space: false,
+            field_prefix: "ch".to_string(),
+            ..KeyValueExtractor::default()
+        };
+        assert_eq!(
+            key_values(&stage, "volt 3.3 amp=1.5 2"),
+            pairs(&[("ch0", 3.3), ("amp", 1.5), ("ch1", 2.0)])
+        );
+    }
+
+    #[test]
+    fn checksums_match_standard_check_values() {
+        let cases = [
+            (ChecksumKind::Crc8, 0xF4),
+            (ChecksumKind::Crc16Ccitt, 0x29B1),
+            (ChecksumKind::Crc16Modbus, 0x4B37),
+            (ChecksumKind::Crc32, 0xCBF4_3926),
+            (ChecksumKind::Sum8, 0xDD),
+            (ChecksumKind::Xor8, 0x31),
+        ];
+        for (kind, check) in cases {
+            assert_eq!(kind.compute(b"123456789"), check, "{}", kind.name());
+        }
+    }
+
+    fn checksum_stage(params: Value) -> Result<Box<dyn Stage + Send + Sync>, String> {
+        let params = StageParams::resolve(&ChecksumStage::descriptor().params, &params)?;
+        ChecksumStage::build(&params)
+    }
+
+    fn is_crc_error(item: &PipelineItem) -> bool {
+        matches!(item, PipelineItem::Packet(packet) if packet.tags.iter().any(|tag| tag == "crc_error"))
+    }
+
+    #[test]
+    fn checksum_offset_must_follow_covered_range() {
+        let err = checksum_stage(json!({ "covered_from": 2, "offset": 2 }))
+            .err()
+            .unwrap();
+        assert!(err.contains("covered_from"), "{err}");
+    }
+
+    #[test]
+    fn header_checksum_covers_trailing_bytes_when_asked() {
+        // 帧头 0xAA，sum8 校验值在偏移 1，其后为负载
+        let frame = vec![0xAA, 0x06, 0x01, 0x02, 0x03];
+        let mut header_only =
+            checksum_stage(json!({ "algorithm": "sum8", "covered_from": 0, "offset": 1 })).unwrap();
+        let mut with_trailing = checksum_stage(json!({
+            "algorithm": "sum8",
+            "covered_from": 0,
+            "offset": 1,
+            "include_trailing": true,
+        }))
+        .unwrap();
+        let packet = |raw: Vec<u8>| PipelineItem::Packet(DataPacket::new("s", raw, None));
+        // 0xAA + 0x01 + 0x02 + 0x03 = 0xB0
+        let mut good = frame.clone();
+        good[1] = 0xB0;
+        assert!(!is_crc_error(
+            &with_trailing.process(packet(good.clone()))[0]
+        ));
+        assert!(is_crc_error(&with_trailing.process(packet(frame))[0]));
+        // 不覆盖负载时只校验帧头
+        assert!(is_crc_error(&header_only.process(packet(good.clone()))[0]));
+        good[1] = 0xAA;
+        assert!(!is_crc_error(&header_only.process(packet(good))[0]));
+    }
+
+    #[test]
+    fn strip_keeps_text_only_when_it_mirrors_raw() {
+        let mut stage = checksum_stage(json!({ "algorithm": "xor8", "strip": true })).unwrap();
+        let strip = |stage: &mut Box<dyn Stage + Send + Sync>, raw: &[u8], text: &str| {
+            let packet = DataPacket::new("s", raw.to_vec(), Some(text.to_string()));
+            match stage.process(PipelineItem::Packet(packet)).remove(0) {
+                PipelineItem::Packet(packet) => (packet.raw, packet.text),
+                _ => panic!("expected a packet"),
+            }
+        };
+
+        // 'A' ^ 'B' = 0x03
+        assert_eq!(
+            strip(&mut stage, b"AB\x03", "AB\u{3}"),
+            (b"AB".to_vec(), Some("AB".to_string()))
+        );
+        // GBK 的 "温" 为 CE C2，校验值 0x0C
+        assert_eq!(
+            strip(&mut stage, &[0xCE, 0xC2, 0x0C], "温\u{c}"),
+            (vec![0xCE, 0xC2], None)
+        );
     }
-    metrics
 }
@@ -0,0 +1,297 @@
+// 在任意文本中扫描数字：支持正负号、小数、指数、0x/0b 整数、nan/inf 以及紧跟的单位后缀
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScanOptions {
+    pub units: bool,
+    pub radix_prefixes: bool,
+    pub special_values: bool,
+}
+
+impl Default for ScanOptions {
+    fn default() -> Self {
+        Self {
+            units: true,
+            radix_prefixes: true,
+            special_values: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct NumberToken {
+    pub value: f64,
+    // 字符（char）下标，左闭右开，不含单位
+    pub start: usize,
+    pub end: usize,
+    pub unit: Option<String>,
+}
+
+pub fn scan_numbers(text: &str, options: ScanOptions) -> Vec<NumberToken> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        if !at_boundary(&chars, i) {
+            i += 1;
+            continue;
+        }
+        match scan_at(&chars, i, options) {
+            Some((value, end)) => {
+                let (unit, next) = if options.units {
+                    scan_unit(&chars, end)
+                } else {
+                    (None, end)
+                };
+                tokens.push(NumberToken {
+                    value,
+                    start: i,
+                    end,
+                    unit,
+                });
+                i = next.max(i + 1);
+            }
+            None => i += 1,
+        }
+    }
+    tokens
+}
+
+// 数字不能紧贴在标识符或另一个数字后面，例如 "ch1"、"1.2.3" 中的 "3"
+fn at_boundary(chars: &[char], i: usize) -> bool {
+    match i.checked_sub(1).map(|prev| chars[prev]) {
+        None => true,
+        Some(prev) => !(prev.is_alphanumeric() || prev == '_' || prev == '.'),
+    }
+}
+
+fn scan_at(chars: &[char], start: usize, options: ScanOptions) -> Option<(f64, usize)> {
+    let mut i = start;
+    let negative = match chars.get(i) {
+        Some('-') => {
+            i += 1;
+            true
+        }
+        Some('+') => {
+            i += 1;
+            false
+        }
+        _ => false,
+    };
+    let sign = if negative { -1.0 } else { 1.0 };
+
+    if options.special_values {
+        if let Some((value, end)) = scan_special(chars, i) {
+            return Some((sign * value, end));
+        }
+    }
+    if options.radix_prefixes {
+        if let Some(scanned) = scan_radix(chars, i) {
+            return scanned.map(|(value, end)| (sign * value, end));
+        }
+    }
+    scan_decimal(chars, i).map(|(value, end)| (sign * value, end))
+}
+
+fn scan_special(chars: &[char], start: usize) -> Option<(f64, usize)> {
+    for (word, value) in [
+        ("infinity", f64::INFINITY),
+        ("inf", f64::INFINITY),
+        ("nan", f64::NAN),
+    ] {
+        let end = start + word.len();
+        if end > chars.len() {
+            continue;
+        }
+        let matched = chars[start..end]
+            .iter()
+            .zip(word.chars())
+            .all(|(c, w)| c.to_ascii_lowercase() == w);
+        let bounded = chars
+            .get(end)
+            .map(|next| !(next.is_alphanumeric() || *next == '_'))
+            .unwrap_or(true);
+        if matched && bounded {
+            return Some((value, end));
+        }
+    }
+    None
+}
+
+// 不是进制前缀时返回 None；前缀后跟着本进制以外的数字（如 "0b102"）或溢出时返回 Some(None)，整段不算数字
+fn scan_radix(chars: &[char], start: usize) -> Option<Option<(f64, usize)>> {
+    if chars.get(start) != Some(&'0') {
+        return None;
+    }
+    let radix = match chars.get(start + 1) {
+        Some('x') | Some('X') => 16,
+        Some('b') | Some('B') => 2,
+        Some('o') | Some('O') => 8,
+        _ => return None,
+    };
+    let digits_start = start + 2;
+    let mut end = digits_start;
+    while end < chars.len() && (chars[end].is_digit(radix) || chars[end] == '_') {
+        end += 1;
+    }
+    let digits: String = chars[digits_start..end]
+        .iter()
+        .filter(|c| **c != '_')
+        .collect();
+    if digits.is_empty() {
+        return None;
+    }
+    if chars.get(end).is_some_and(char::is_ascii_digit) {
+        return Some(None);
+    }
+    Some(
+        u64::from_str_radix(&digits, radix)
+            .ok()
+            .map(|value| (value as f64, end)),
+    )
+}
+
+fn scan_decimal(chars: &[char], start: usize) -> Option<(f64, usize)> {
+    let mut i = start;
+    let int_start = i;
+    while i < chars.len() && chars[i].is_ascii_digit() {
+        i += 1;
+    }
+    let int_digits = i - int_start;
+
+    let mut frac_digits = 0;
+    if chars.get(i) == Some(&'.') {
+        let frac_start = i + 1;
+        let mut j = frac_start;
+        while j < chars.len() && chars[j].is_ascii_digit() {
+            j += 1;
+        }
+        frac_digits = j - frac_start;
+        if int_digits > 0 || frac_digits > 0 {
+            i = j;
+        }
+    }
+    if int_digits == 0 && frac_digits == 0 {
+        return None;
+    }
+
+    // 只有 e 后面确实跟着数字时才当作指数，避免把 "5em" 之类的单位吃掉
+    if matches!(chars.get(i), Some('e') | Some('E')) {
+        let mut j = i + 1;
+        if matches!(chars.get(j), Some('+') | Some('-')) {
+            j += 1;
+        }
+        let exp_start = j;
+        while j < chars.len() && chars[j].is_ascii_digit() {
+            j += 1;
+        }
+        if j > exp_start {
+            i = j;
+        }
+    }
+
+    let literal: String = chars[start..i].iter().collect();
+    literal.parse::<f64>().ok().map(|value| (value, i))
+}
+
+fn scan_unit(chars: &[char], start: usize) -> (Option<String>, usize) {
+    let is_unit_char = |c: char| c.is_alphabetic() || matches!(c, '%' | '°' | 'µ' | 'Ω');
+    let mut end = start;
+    while end < chars.len() {
+        let c = chars[end];
+        if is_unit_char(c) {
+            end += 1;
+        } else if c == '/' && end > start && chars.get(end + 1).copied().is_some_and(is_unit_char) {
+            // 复合单位，例如 m/s
+            end += 1;
+        } else {
+            break;
+        }
+    }
+    if end == start {
+        (None, start)
+    } else {
+        (Some(chars[start..end].iter().collect()), end)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type Token = (f64, usize, usize, Option<String>);
+    type Expected = (f64, usize, usize, Option<&'static str>);
+
+    fn scan(text: &str) -> Vec<Token> {
+        scan_numbers(text, ScanOptions::default())
+            .into_iter()
+            .map(|token| (token.value, token.start, token.end, token.unit))
+            .collect()
+    }
+
+    #[test]
+    fn scans_values_units_and_char_spans() {
+        let cases: &[(&str, &[Expected])] = &[
+            ("temp=23.5", &[(23.5, 5, 9, None)]),
+            ("3.3V", &[(3.3, 0, 3, Some("V"))]),
+            ("-1.2e-3,", &[(-1.2e-3, 0, 7, None)]),
+            ("0x1F", &[(31.0, 0, 4, None)]),
+            ("0b1010 0o17", &[(10.0, 0, 6, None), (15.0, 7, 11, None)]),
+            (
+                "v=-3.3V i=+12mA",
+                &[(-3.3, 2, 6, Some("V")), (12.0, 10, 13, Some("mA"))],
+            ),
+            (
+                "5em 9.8m/s",
+                &[(5.0, 0, 1, Some("em")), (9.8, 4, 7, Some("m/s"))],
+            ),
+            ("温度=25°C", &[(25.0, 3, 5, Some("°C"))]),
+            ("ch1 1.2.3", &[(1.2, 4, 7, None)]),
+            (
+                "inf -infinity",
+                &[
+                    (f64::INFINITY, 0, 3, None),
+                    (f64::NEG_INFINITY, 4, 13, None),
+                ],
+            ),
+        ];
+        for (text, expected) in cases {
+            let expected: Vec<Token> = expected
+                .iter()
+                .map(|(value, start, end, unit)| (*value, *start, *end, unit.map(str::to_string)))
+                .collect();
+            assert_eq!(scan(text), expected, "{text}");
+        }
+    }
+
+    #[test]
+    fn nan_is_a_value() {
+        let tokens = scan_numbers("x nan", ScanOptions::default());
+        assert_eq!(tokens.len(), 1);
+        assert!(tokens[0].value.is_nan());
+        assert_eq!((tokens[0].start, tokens[0].end), (2, 5));
+    }
+
+    #[test]
+    fn rejects_radix_literals_with_invalid_digits() {
+        assert!(scan("0b102").is_empty());
+        assert!(scan("0o78").is_empty());
+        assert!(scan("0xFFFFFFFFFFFFFFFFF").is_empty());
+        // 前缀后没有数字时不算进制字面量
+        assert_eq!(scan("0bytes"), vec![(0.0, 0, 1, Some("bytes".to_string()))]);
+    }
+
+    #[test]
+    fn options_disable_extras() {
+        let options = ScanOptions {
+            units: false,
+            radix_prefixes: false,
+            special_values: false,
+        };
+        let tokens = scan_numbers("0x10 nan 3V", options);
+        let values: Vec<(f64, Option<String>)> = tokens
+            .into_iter()
+            .map(|token| (token.value, token.unit))
+            .collect();
+        assert_eq!(values, vec![(0.0, None), (3.0, None)]);
+    }
+}
@@ -0,0 +1,270 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use crate::api::events::{data_stream_event, hex_dump_event, metrics_event};
+use crate::core::channel::{bounded, ChannelConfig, ChannelStats, PacketReceiver, PacketSender};
+use crate::core::encoding::{EncodingConfig, TextDecoder};
+use crate::core::hex_dump::{hex_dump_rows, HexDump, HexDumpConfig};
+use crate::core::traits::SharedWriter;
+use crate::core::types::{DataPacket, PipelineItem, Timestamp};
+use crate::pipeline::Pipeline;
+use crate::services::subscription::SubscriptionHub;
+use crate::services::transaction::ResponseWaiters;
+use crate::services::transmitter::SourceTransmitter;
+
+pub type SharedPipeline = Arc<Mutex<Pipeline>>;
+pub type SharedHub = Arc<OnceLock<SubscriptionHub>>;
+
+// 没有新包时也按这个间隔驱动管线的 tick，让行缓冲等能按空闲超时冲刷
+const TICK_INTERVAL_MS: u64 = 20;
+
+// 解码器里残留的半个字符超过这么久没等到后续字节，就按非法字节输出
+const DECODER_FLUSH_IDLE_MS: u64 = 200;
+
+// 每个 source 一个分发线程：从 source 的 channel 收包，跑挂载的管线，再推送事件
+pub struct Dispatcher {
+    sender: PacketSender,
+    route: Arc<Mutex<Option<SharedPipeline>>>,
+    encoding: Arc<Mutex<Option<EncodingConfig>>>,
+    hex_dump: Arc<Mutex<Option<HexDumpConfig>>>,
+    responses: ResponseWaiters,
+}
+
+impl Dispatcher {
+    pub fn spawn(source_id: &str, hub: SharedHub) -> Result<Self, String> {
+        let (sender, receiver) = bounded(ChannelConfig::default());
+        let route = Arc::new(Mutex::new(None));
+        let encoding = Arc::new(Mutex::new(None));
+        let hex_dump = Arc::new(Mutex::new(None));
+        let responses = ResponseWaiters::default();
+        let worker = Worker {
+            source_id: source_id.to_string(),
+            receiver,
+            route: Arc::clone(&route),
+            encoding: Arc::clone(&encoding),
+            decoders: HashMap::new(),
+            hex_dump: Arc::clone(&hex_dump),
+            stream_offset: 0,
+            responses: responses.clone(),
+            hub,
+        };
+        std::thread::Builder::new()
+            .name(format!("dispatch-{source_id}"))
+            .spawn(move || worker.run())
+            .map_err(|err| err.to_string())?;
+        Ok(Self {
+            sender,
+            route,
+            encoding,
+            hex_dump,
+            responses,
+        })
+    }
+
+    pub fn sender(&self) -> PacketSender {
+        self.sender.clone()
+    }
+
+    pub fn stats(&self) -> ChannelStats {
+        self.sender.stats()
+    }
+
+    pub fn channel_config(&self) -> ChannelConfig {
+        self.sender.config()
+    }
+
+    pub fn set_channel_config(&self, config: ChannelConfig) {
+        self.sender.set_config(config);
+    }
+
+    pub fn set_pipeline(&self, pipeline: Option<SharedPipeline>) {
+        if let Ok(mut route) = self.route.lock() {
+            *route = pipeline;
+        }
+    }
+
+    pub fn encoding(&self) -> Option<EncodingConfig> {
+        self.encoding.lock().ok().and_then(|encoding| *encoding)
+    }
+
+    pub fn set_encoding(&self, config: Option<EncodingConfig>) {
+        if let Ok(mut encoding) = self.encoding.lock() {
+            *encoding = config;
+        }
+    }
+
+    pub fn hex_dump(&self) -> Option<HexDumpConfig> {
+        self.hex_dump.lock().ok().and_then(|hex_dump| *hex_dump)
+    }
+
+    pub fn set_hex_dump(&self, config: Option<HexDumpConfig>) {
+        if let Ok(mut hex_dump) = self.hex_dump.lock() {
+            *hex_dump = config;
+        }
+    }
+
+    pub fn transmitter(&self, source_id: &str, writer: SharedWriter) -> SourceTransmitter {
+        SourceTransmitter::new(
+            source_id,
+            writer,
+            self.sender.clone(),
+            Arc::clone(&self.encoding),
+            self.responses.clone(),
+        )
+    }
+
+    pub fn inject(&self, packet: DataPacket) -> Result<(), String> {
+        self.sender
+            .send(packet)
+            .map_err(|_| "dispatcher stopped".to_string())
+    }
+}
+
+struct Worker {
+    source_id: String,
+    receiver: PacketReceiver,
+    route: Arc<Mutex<Option<SharedPipeline>>>,
+    encoding: Arc<Mutex<Option<EncodingConfig>>>,
+    // 按 tags 区分（TCP server 的多个 peer），避免不同连接的半个字符拼在一起
+    // 附带最近一次解码的时间，用于空闲时冲刷残留字节
+    decoders: HashMap<Vec<String>, (TextDecoder, Timestamp)>,
+    hex_dump: Arc<Mutex<Option<HexDumpConfig>>>,
+    // 该 source 已收到的字节数，作为 hex dump 的偏移
+    stream_offset: u64,
+    responses: ResponseWaiters,
+    hub: SharedHub,
+}
+
+impl Worker {
+    // 所有 Sender（source 与 Dispatcher 自身）都被丢弃后 recv_timeout 返回 Err，线程随之退出
+    fn run(mut self) {
+        let stream_event = data_stream_event(&self.source_id);
+        let hex_dump_event = hex_dump_event(&self.source_id);
+        let tick = Duration::from_millis(TICK_INTERVAL_MS);
+        let mut last_tick = Instant::now();
+        while let Ok(mut received) = self.receiver.recv_timeout(tick) {
+            // 空闲时把等不到后续字节的残留字符作为一个只有 text 的包放出来
+            if received.is_none() {
+                received = self.flush_decoder();
+            }
+            if let Some(packet) = received.as_mut() {
+                self.decode(packet);
+            }
+            if let (Some(packet), Some(hub)) = (&received, self.hub.get()) {
+                let _ = hub.emit_packet(&stream_event, packet);
+            }
+            // 发送回显只推送到 data_stream，不计入接收字节偏移，也不交给解析管线
+            if received.as_ref().is_some_and(DataPacket::is_tx) {
+                received = None;
+            }
+            if let Some(packet) = &received {
+                self.emit_hex_dump(&hex_dump_event, packet);
+            }
+
+            let pipeline = self.route.lock().ok().and_then(|route| route.clone());
+            // 挂了管线时按管线输出的行/帧匹配应答，否则按原始包
+            let Some(pipeline) = pipeline else {
+                if let Some(packet) = &received {
+                    self.responses.offer(packet);
+                }
+                continue;
+            };
+            let (pipeline_id, outputs) = match pipeline.lock() {
+                Ok(mut pipeline) => {
+                    let mut outputs = match received {
+                        Some(packet) => pipeline.process(PipelineItem::Packet(packet)),
+                        None => Vec::new(),
+                    };
+                    // 数据持续到达时也要按间隔 tick，否则其它 peer 的残留行永远等不到冲刷
+                    if last_tick.elapsed() >= tick {
+                        last_tick = Instant::now();
+                        outputs.extend(pipeline.tick(&self.source_id, Timestamp::now()));
+                    }
+                    (pipeline.id().to_string(), outputs)
+                }
+                Err(_) => continue,
+            };
+            for item in &outputs {
+                if let PipelineItem::Packet(packet) = item {
+                    self.responses.offer(packet);
+                }
+            }
+            if outputs.is_empty() {
+                continue;
+            }
+
+            let Some(hub) = self.hub.get() else {
+                continue;
+            };
+            let metrics = metrics_event(&pipeline_id);
+            for item in outputs {
+                let _ = match item {
+                    PipelineItem::Metric(metric) => hub.emit_metric(&metrics, &metric),
+                    PipelineItem::Event(event) => hub.emit_event(&stream_event, &event),
+                    PipelineItem::Packet(packet) => hub.emit_packet(&stream_event, &packet),
+                };
+            }
+        }
+    }
+
+    // 配置了 source 级编码时，为还没有 text 的包解码
+    fn decode(&mut self, packet: &mut DataPacket) {
+        let Some(config) = self.encoding.lock().ok().and_then(|encoding| *encoding) else {
+            self.decoders.clear();
+            return;
+        };
+        if packet.text.is_some() {
+            return;
+        }
+        let (decoder, last_ts) = self
+            .decoders
+            .entry(packet.tags.clone())
+            .or_insert_with(|| (TextDecoder::new(config), packet.ts));
+        if decoder.config() != config {
+            *decoder = TextDecoder::new(config);
+        }
+        *last_ts = packet.ts;
+        packet.text = Some(decoder.decode(&packet.raw));
+    }
+
+    fn flush_decoder(&mut self) -> Option<DataPacket> {
+        let now = Timestamp::now();
+        let idle_micros = DECODER_FLUSH_IDLE_MS * 1000;
+        let source_id = &self.source_id;
+        self.decoders
+            .iter_mut()
+            .find_map(|(tags, (decoder, last_ts))| {
+                if now.mono_micros.saturating_sub(last_ts.mono_micros) < idle_micros {
+                    return None;
+                }
+                let text = decoder.flush()?;
+                let mut packet =
+                    DataPacket::new(source_id.clone(), Vec::new(), Some(text)).with_timestamp(now);
+                packet.tags = tags.clone();
+                Some(packet)
+            })
+    }
+
+    fn emit_hex_dump(&mut self, event: &str, packet: &DataPacket) {
+        let offset = self.stream_offset;
+        self.stream_offset += packet.raw.len() as u64;
+        // 冲刷解码器产生的包没有原始字节
+        if packet.raw.is_empty() {
+            return;
+        }
+        let Some(config) = self.hex_dump.lock().ok().and_then(|hex_dump| *hex_dump) else {
+            return;
+        };
+        let Some(hub) = self.hub.get() else {
+            return;
+        };
+        let dump = HexDump {
+            ts: packet.ts,
+            source_id: packet.source_id.clone(),
+            tags: packet.tags.clone(),
+            rows: hex_dump_rows(&packet.raw, offset, &config),
+        };
+        let _ = hub.emit_hex_dump(event, &dump);
+    }
+}
@@ -1,30 +1,46 @@
+pub mod dispatcher;
+pub mod port_watcher;
 pub mod registry;
+pub mod scheduler;
 pub mod stream_manager;
 pub mod subscription;
+pub mod transaction;
+pub mod transmitter;
 
 use std::sync::{Arc, Mutex};
 
+pub use port_watcher::PortWatcher;
 #[allow(unused_imports)]
 pub use registry::ParserRegistry;
+pub use scheduler::TransmitScheduler;
 pub use stream_manager::StreamManager;
-#[allow(unused_imports)]
 pub use subscription::SubscriptionHub;
 
 use crate::sources::{NetworkSource, RttSource, SerialSource};
 
 pub struct AppState {
     pub manager: Arc<Mutex<StreamManager>>,
+    pub scheduler: Mutex<TransmitScheduler>,
 }
 
 impl AppState {
     pub fn new() -> Self {
         let mut manager = StreamManager::new();
-        manager.add_source(Box::new(SerialSource::new("serial", "Serial")));
-        manager.add_source(Box::new(RttSource::new("rtt", "RTT")));
-        manager.add_source(Box::new(NetworkSource::new("network", "Network")));
+        manager
+            .add_source(Box::new(SerialSource::new("serial", "Serial")))
+            .expect("failed to register serial source");
+        manager
+            .add_source(Box::new(RttSource::new("rtt", "RTT")))
+            .expect("failed to register rtt source");
+        manager
+            .add_source(Box::new(NetworkSource::new("network", "Network")))
+            .expect("failed to register network source");
 
+        let hub = manager.hub();
+        let manager = Arc::new(Mutex::new(manager));
         Self {
-            manager: Arc::new(Mutex::new(manager)),
+            scheduler: Mutex::new(TransmitScheduler::new(Arc::clone(&manager), hub)),
+            manager,
         }
     }
 }
@@ -0,0 +1,73 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tauri::{AppHandle, Emitter};
+
+use crate::api::events::SERIAL_PORTS_CHANGED;
+use crate::core::types::{SerialPortInfo, SerialPortsChanged};
+use crate::services::StreamManager;
+use crate::sources::serial::available_ports;
+
+const POLL_INTERVAL_MS: u64 = 1000;
+
+pub struct PortWatcher {
+    known: Vec<SerialPortInfo>,
+}
+
+impl PortWatcher {
+    pub fn new() -> Self {
+        Self {
+            known: available_ports().unwrap_or_default(),
+        }
+    }
+
+    pub fn spawn(app: AppHandle, manager: Arc<Mutex<StreamManager>>) -> Result<(), String> {
+        let mut watcher = Self::new();
+        std::thread::Builder::new()
+            .name("serial-port-watcher".to_string())
+            .spawn(move || loop {
+                std::thread::sleep(Duration::from_millis(POLL_INTERVAL_MS));
+                let Some(mut change) = watcher.poll() else {
+                    continue;
+                };
+
+                if let Ok(mut manager) = manager.lock() {
+                    for device in &change.removed {
+                        change
+                            .disconnected_sources
+                            .extend(manager.handle_device_removed(device));
+                    }
+                }
+                let _ = app.emit(SERIAL_PORTS_CHANGED, &change);
+            })
+            .map(|_| ())
+            .map_err(|err| err.to_string())
+    }
+
+    pub fn poll(&mut self) -> Option<SerialPortsChanged> {
+        let ports = available_ports().ok()?;
+        let added: Vec<SerialPortInfo> = ports
+            .iter()
+            .filter(|port| !self.known.iter().any(|known| known.name == port.name))
+            .cloned()
+            .collect();
+        let removed: Vec<String> = self
+            .known
+            .iter()
+            .filter(|known| !ports.iter().any(|port| port.name == known.name))
+            .map(|known| known.name.clone())
+            .collect();
+
+        if added.is_empty() && removed.is_empty() {
+            return None;
+        }
+
+        self.known = ports.clone();
+        Some(SerialPortsChanged {
+            added,
+            removed,
+            ports,
+            disconnected_sources: Vec::new(),
+        })
+    }
+}
@@ -1,30 +1,100 @@
+use serde_json::Value;
+
 use crate::core::types::ParserDescriptor;
+use crate::pipeline::ansi::AnsiStage;
+use crate::pipeline::capture::RegexStage;
+use crate::pipeline::csv::CsvStage;
+use crate::pipeline::decode::DecodeStage;
+use crate::pipeline::framing::FramingStage;
+use crate::pipeline::json_line::JsonLineStage;
+use crate::pipeline::layout::StructDecoder;
+use crate::pipeline::log_level::LogLevelStage;
+use crate::pipeline::params::StageParams;
+use crate::pipeline::stages::{
+    ChecksumStage, FloatExtractor, KeyValueExtractor, LineSplitter, Stage,
+};
+use crate::pipeline::{Pipeline, PipelineSpec};
+
+pub type StageFactory = fn(&StageParams) -> Result<Box<dyn Stage + Send + Sync>, String>;
+
+struct RegistryEntry {
+    descriptor: ParserDescriptor,
+    factory: StageFactory,
+}
+
+impl RegistryEntry {
+    fn new(descriptor: ParserDescriptor, factory: StageFactory) -> Self {
+        Self {
+            descriptor,
+            factory,
+        }
+    }
+}
 
 pub struct ParserRegistry {
-    parsers: Vec<ParserDescriptor>,
+    entries: Vec<RegistryEntry>,
 }
 
 impl ParserRegistry {
     pub fn new() -> Self {
-        let parsers = vec![
-            ParserDescriptor {
-                id: "line_splitter".to_string(),
-                label: "Line Splitter".to_string(),
-                kind: "stage".to_string(),
-                configurable: false,
-            },
-            ParserDescriptor {
-                id: "float_extractor".to_string(),
-                label: "Float Extractor".to_string(),
-                kind: "stage".to_string(),
-                configurable: false,
-            },
+        // 每个 stage 的参数说明和构造都在各自模块里，这里只决定列表顺序
+        let entries = vec![
+            RegistryEntry::new(LineSplitter::descriptor(), LineSplitter::build),
+            RegistryEntry::new(FloatExtractor::descriptor(), FloatExtractor::build),
+            RegistryEntry::new(KeyValueExtractor::descriptor(), KeyValueExtractor::build),
+            RegistryEntry::new(FramingStage::slip_descriptor(), FramingStage::build_slip),
+            RegistryEntry::new(FramingStage::cobs_descriptor(), FramingStage::build_cobs),
+            RegistryEntry::new(FramingStage::fixed_descriptor(), FramingStage::build_fixed),
+            RegistryEntry::new(
+                FramingStage::length_prefix_descriptor(),
+                FramingStage::build_length_prefix,
+            ),
+            RegistryEntry::new(
+                FramingStage::delimited_descriptor(),
+                FramingStage::build_delimited,
+            ),
+            RegistryEntry::new(ChecksumStage::descriptor(), ChecksumStage::build),
+            RegistryEntry::new(StructDecoder::descriptor(), StructDecoder::build),
+            RegistryEntry::new(JsonLineStage::descriptor(), JsonLineStage::build),
+            RegistryEntry::new(RegexStage::descriptor(), RegexStage::build),
+            RegistryEntry::new(CsvStage::descriptor(), CsvStage::build),
+            RegistryEntry::new(LogLevelStage::descriptor(), LogLevelStage::build),
+            RegistryEntry::new(AnsiStage::descriptor(), AnsiStage::build),
+            RegistryEntry::new(DecodeStage::descriptor(), DecodeStage::build),
         ];
 
-        Self { parsers }
+        Self { entries }
     }
 
     pub fn list(&self) -> Vec<ParserDescriptor> {
-        self.parsers.clone()
+        self.entries
+            .iter()
+            .map(|entry| entry.descriptor.clone())
+            .collect()
+    }
+
+    pub fn build_stage(
+        &self,
+        stage_id: &str,
+        params: &Value,
+    ) -> Result<Box<dyn Stage + Send + Sync>, String> {
+        let entry = self
+            .entries
+            .iter()
+            .find(|entry| entry.descriptor.id == stage_id)
+            .ok_or_else(|| "not registered in ParserRegistry".to_string())?;
+        let params = StageParams::resolve(&entry.descriptor.params, params)?;
+        (entry.factory)(&params)
+    }
+
+    pub fn build_pipeline(&self, spec: &PipelineSpec) -> Result<Pipeline, String> {
+        let mut pipeline = Pipeline::new(&spec.id);
+        for (index, stage) in spec.stages.iter().enumerate() {
+            let built = self
+                .build_stage(&stage.id, &stage.params)
+                .map_err(|err| format!("stages[{index}] ({}): {err}", stage.id))?;
+            pipeline.push_stage(built);
+        }
+        Ok(pipeline)
     }
 }
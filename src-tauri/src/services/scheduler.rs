@@ -0,0 +1,607 @@
+// 定时发送：每个任务一个线程，启动时从 StreamManager 取出发送句柄，之后按计划写入 source 不再占用它的锁
+use std::collections::HashMap;
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use crate::api::events::TRANSMIT_JOB_CHANGED;
+use crate::core::payload::PayloadEncoding;
+use crate::core::types::{Timestamp, TransmitJobInfo, TransmitJobState};
+use crate::services::dispatcher::SharedHub;
+use crate::services::transmitter::SourceTransmitter;
+use crate::services::StreamManager;
+
+// 任务结束（完成、停止或失败）时调用，默认推送 transmit_job::changed 事件
+pub type JobListener = Arc<dyn Fn(&TransmitJobInfo) + Send + Sync>;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransmitMessage {
+    pub payload: String,
+    #[serde(default)]
+    pub encoding: PayloadEncoding,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SequenceStep {
+    #[serde(flatten)]
+    pub message: TransmitMessage,
+    // 发送本条之后、下一条之前的等待时间
+    #[serde(default)]
+    pub delay_ms: u64,
+}
+
+fn default_cycles() -> u64 {
+    1
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum TransmitSchedule {
+    // count 为 0 时一直发送直到被停止
+    Periodic {
+        message: TransmitMessage,
+        interval_ms: u64,
+        #[serde(default)]
+        count: u64,
+    },
+    // cycles 为 0 时循环直到被停止
+    Sequence {
+        steps: Vec<SequenceStep>,
+        #[serde(default = "default_cycles")]
+        cycles: u64,
+    },
+    // 时间已过时立即发送
+    OneShot {
+        message: TransmitMessage,
+        at_unix_millis: u64,
+    },
+}
+
+impl TransmitSchedule {
+    pub fn name(&self) -> &'static str {
+        match self {
+            TransmitSchedule::Periodic { .. } => "periodic",
+            TransmitSchedule::Sequence { .. } => "sequence",
+            TransmitSchedule::OneShot { .. } => "one_shot",
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            TransmitSchedule::Periodic { interval_ms, .. } => {
+                if *interval_ms == 0 {
+                    return Err("interval_ms must be greater than 0".to_string());
+                }
+            }
+            TransmitSchedule::Sequence { steps, .. } => {
+                if steps.is_empty() {
+                    return Err("sequence must contain at least one step".to_string());
+                }
+                // 全部延时为 0 的无限循环会把 source 写满
+                if steps.iter().all(|step| step.delay_ms == 0) && self.is_unbounded() {
+                    return Err("endless sequence needs at least one non-zero delay_ms".to_string());
+                }
+            }
+            TransmitSchedule::OneShot { .. } => {}
+        }
+        Ok(())
+    }
+
+    fn is_unbounded(&self) -> bool {
+        matches!(
+            self,
+            TransmitSchedule::Periodic { count: 0, .. }
+                | TransmitSchedule::Sequence { cycles: 0, .. }
+        )
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransmitJobSpec {
+    pub id: String,
+    pub source_id: String,
+    #[serde(flatten)]
+    pub schedule: TransmitSchedule,
+}
+
+#[derive(Default)]
+struct StopSignal {
+    stopped: Mutex<bool>,
+    wake: Condvar,
+}
+
+impl StopSignal {
+    fn stop(&self) {
+        if let Ok(mut stopped) = self.stopped.lock() {
+            *stopped = true;
+        }
+        self.wake.notify_all();
+    }
+
+    // 等到 deadline 或被停止；返回 true 表示已停止
+    fn wait_until(&self, deadline: Instant) -> bool {
+        let Ok(mut stopped) = self.stopped.lock() else {
+            return true;
+        };
+        loop {
+            if *stopped {
+                return true;
+            }
+            let now = Instant::now();
+            if now >= deadline {
+                return false;
+            }
+            stopped = match self.wake.wait_timeout(stopped, deadline - now) {
+                Ok((stopped, _)) => stopped,
+                Err(_) => return true,
+            };
+        }
+    }
+}
+
+struct TransmitJob {
+    info: Arc<Mutex<TransmitJobInfo>>,
+    stop: Arc<StopSignal>,
+    worker: Option<JoinHandle<()>>,
+}
+
+impl TransmitJob {
+    fn info(&self) -> Option<TransmitJobInfo> {
+        self.info.lock().ok().map(|info| info.clone())
+    }
+
+    fn is_running(&self) -> bool {
+        self.worker
+            .as_ref()
+            .map(|worker| !worker.is_finished())
+            .unwrap_or(false)
+    }
+
+    fn stop(&mut self) {
+        self.stop.stop();
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+pub struct TransmitScheduler {
+    manager: Arc<Mutex<StreamManager>>,
+    listener: JobListener,
+    jobs: HashMap<String, TransmitJob>,
+}
+
+impl TransmitScheduler {
+    pub fn new(manager: Arc<Mutex<StreamManager>>, hub: SharedHub) -> Self {
+        Self::with_listener(
+            manager,
+            Arc::new(move |info| {
+                if let Some(hub) = hub.get() {
+                    let _ = hub.emit_transmit_job(TRANSMIT_JOB_CHANGED, info);
+                }
+            }),
+        )
+    }
+
+    pub fn with_listener(manager: Arc<Mutex<StreamManager>>, listener: JobListener) -> Self {
+        Self {
+            manager,
+            listener,
+            jobs: HashMap::new(),
+        }
+    }
+
+    pub fn list_jobs(&self) -> Vec<TransmitJobInfo> {
+        let mut jobs: Vec<TransmitJobInfo> =
+            self.jobs.values().filter_map(TransmitJob::info).collect();
+        jobs.sort_by(|a, b| a.id.cmp(&b.id));
+        jobs
+    }
+
+    pub fn start_job(&mut self, spec: TransmitJobSpec) -> Result<TransmitJobInfo, String> {
+        if spec.id.trim().is_empty() {
+            return Err("job id must not be empty".to_string());
+        }
+        spec.schedule.validate()?;
+        if self.jobs.get(&spec.id).is_some_and(TransmitJob::is_running) {
+            return Err(format!("job already running: {}", spec.id));
+        }
+        // 不支持写入的 source 在这里就报错，不会启动一个注定失败的任务
+        let transmitter = self
+            .manager
+            .lock()
+            .map_err(|_| "lock poisoned".to_string())?
+            .transmitter(&spec.source_id)?;
+        self.spawn_job(spec, transmitter)
+    }
+
+    fn spawn_job(
+        &mut self,
+        spec: TransmitJobSpec,
+        transmitter: SourceTransmitter,
+    ) -> Result<TransmitJobInfo, String> {
+        let info = TransmitJobInfo {
+            id: spec.id.clone(),
+            source_id: spec.source_id.clone(),
+            mode: spec.schedule.name().to_string(),
+            state: TransmitJobState::Running,
+            sent: 0,
+        };
+        let job_info = Arc::new(Mutex::new(info.clone()));
+        let stop = Arc::new(StopSignal::default());
+        let runner = JobRunner {
+            spec,
+            transmitter,
+            listener: Arc::clone(&self.listener),
+            info: Arc::clone(&job_info),
+            stop: Arc::clone(&stop),
+        };
+        let worker = std::thread::Builder::new()
+            .name(format!("tx-job-{}", info.id))
+            .spawn(move || runner.run())
+            .map_err(|err| err.to_string())?;
+
+        self.jobs.insert(
+            info.id.clone(),
+            TransmitJob {
+                info: job_info,
+                stop,
+                worker: Some(worker),
+            },
+        );
+        Ok(info)
+    }
+
+    // 停止并移除任务；已结束的任务也通过这里清除
+    pub fn stop_job(&mut self, job_id: &str) -> Result<(), String> {
+        let mut job = self
+            .jobs
+            .remove(job_id)
+            .ok_or_else(|| format!("job not found: {job_id}"))?;
+        job.stop();
+        Ok(())
+    }
+
+    // source 被删除时调用，停止并移除它的所有任务
+    pub fn stop_source_jobs(&mut self, source_id: &str) {
+        let job_ids: Vec<String> = self
+            .jobs
+            .iter()
+            .filter(|(_, job)| job.info().is_some_and(|info| info.source_id == source_id))
+            .map(|(id, _)| id.clone())
+            .collect();
+        for job_id in job_ids {
+            let _ = self.stop_job(&job_id);
+        }
+    }
+}
+
+impl Drop for TransmitScheduler {
+    fn drop(&mut self) {
+        for job in self.jobs.values_mut() {
+            job.stop();
+        }
+    }
+}
+
+struct JobRunner {
+    spec: TransmitJobSpec,
+    transmitter: SourceTransmitter,
+    listener: JobListener,
+    info: Arc<Mutex<TransmitJobInfo>>,
+    stop: Arc<StopSignal>,
+}
+
+impl JobRunner {
+    fn run(self) {
+        let state = match self.execute() {
+            Ok(true) => TransmitJobState::Completed,
+            Ok(false) => TransmitJobState::Stopped,
+            Err(err) => TransmitJobState::Failed(err),
+        };
+        let info = match self.info.lock() {
+            Ok(mut info) => {
+                info.state = state;
+                info.clone()
+            }
+            Err(_) => return,
+        };
+        (self.listener)(&info);
+    }
+
+    // Ok(true) 表示按计划发送完毕，Ok(false) 表示中途被停止
+    fn execute(&self) -> Result<bool, String> {
+        match &self.spec.schedule {
+            TransmitSchedule::Periodic {
+                message,
+                interval_ms,
+                count,
+            } => {
+                let interval = Duration::from_millis(*interval_ms);
+                let mut next = Instant::now();
+                let mut sent = 0;
+                while *count == 0 || sent < *count {
+                    if self.stop.wait_until(next) {
+                        return Ok(false);
+                    }
+                    self.send(message)?;
+                    sent += 1;
+                    // 按起始时间累加避免漂移；落后超过一个周期时不补发
+                    next += interval;
+                    next = next.max(Instant::now());
+                }
+                Ok(true)
+            }
+            TransmitSchedule::Sequence { steps, cycles } => {
+                let mut cycle = 0;
+                while *cycles == 0 || cycle < *cycles {
+                    for step in steps {
+                        if self.stop.wait_until(Instant::now()) {
+                            return Ok(false);
+                        }
+                        self.send(&step.message)?;
+                        if self
+                            .stop
+                            .wait_until(Instant::now() + Duration::from_millis(step.delay_ms))
+                        {
+                            return Ok(false);
+                        }
+                    }
+                    cycle += 1;
+                }
+                Ok(true)
+            }
+            TransmitSchedule::OneShot {
+                message,
+                at_unix_millis,
+            } => {
+                let delay = at_unix_millis.saturating_sub(Timestamp::now().unix_millis());
+                if self
+                    .stop
+                    .wait_until(Instant::now() + Duration::from_millis(delay))
+                {
+                    return Ok(false);
+                }
+                self.send(message)?;
+                Ok(true)
+            }
+        }
+    }
+
+    fn send(&self, message: &TransmitMessage) -> Result<(), String> {
+        self.transmitter.send(&message.payload, message.encoding)?;
+        if let Ok(mut info) = self.info.lock() {
+            info.sent += 1;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::channel::{bounded, ChannelConfig, PacketReceiver};
+    use crate::core::traits::SourceWriter;
+    use crate::services::transaction::ResponseWaiters;
+
+    const TIMEOUT: Duration = Duration::from_secs(2);
+
+    #[derive(Default)]
+    struct RecordingWriter {
+        writes: Mutex<Vec<(Instant, Vec<u8>)>>,
+        fail: bool,
+    }
+
+    impl RecordingWriter {
+        fn payloads(&self) -> Vec<Vec<u8>> {
+            let writes = self.writes.lock().unwrap();
+            writes.iter().map(|(_, data)| data.clone()).collect()
+        }
+    }
+
+    impl SourceWriter for RecordingWriter {
+        fn write(&self, data: &[u8]) -> Result<(), String> {
+            if self.fail {
+                return Err("write failed: device gone".to_string());
+            }
+            let mut writes = self.writes.lock().unwrap();
+            writes.push((Instant::now(), data.to_vec()));
+            Ok(())
+        }
+    }
+
+    struct Fixture {
+        scheduler: TransmitScheduler,
+        changes: Arc<Mutex<Vec<TransmitJobInfo>>>,
+        // 持有接收端，回显包才能送出
+        receivers: Vec<PacketReceiver>,
+    }
+
+    impl Fixture {
+        fn new() -> Self {
+            let changes = Arc::new(Mutex::new(Vec::new()));
+            let recorded = Arc::clone(&changes);
+            let scheduler = TransmitScheduler::with_listener(
+                Arc::new(Mutex::new(StreamManager::new())),
+                Arc::new(move |info: &TransmitJobInfo| {
+                    recorded.lock().unwrap().push(info.clone());
+                }),
+            );
+            Self {
+                scheduler,
+                changes,
+                receivers: Vec::new(),
+            }
+        }
+
+        fn start(
+            &mut self,
+            id: &str,
+            source_id: &str,
+            schedule: TransmitSchedule,
+            writer: Arc<RecordingWriter>,
+        ) -> TransmitJobInfo {
+            let (sender, receiver) = bounded(ChannelConfig::default());
+            self.receivers.push(receiver);
+            let transmitter = SourceTransmitter::new(
+                source_id,
+                writer,
+                sender,
+                Arc::new(Mutex::new(None)),
+                ResponseWaiters::default(),
+            );
+            let spec = TransmitJobSpec {
+                id: id.to_string(),
+                source_id: source_id.to_string(),
+                schedule,
+            };
+            self.scheduler.spawn_job(spec, transmitter).unwrap()
+        }
+
+        // 等到任务结束并推送了状态变化
+        fn wait_changed(&self, id: &str) -> TransmitJobInfo {
+            let deadline = Instant::now() + TIMEOUT;
+            loop {
+                let changes = self.changes.lock().unwrap();
+                if let Some(info) = changes.iter().find(|info| info.id == id) {
+                    return info.clone();
+                }
+                drop(changes);
+                assert!(Instant::now() < deadline, "job {id} did not finish");
+                std::thread::sleep(Duration::from_millis(5));
+            }
+        }
+    }
+
+    fn message(payload: &str) -> TransmitMessage {
+        TransmitMessage {
+            payload: payload.to_string(),
+            encoding: PayloadEncoding::default(),
+        }
+    }
+
+    #[test]
+    fn periodic_job_sends_count_messages_on_interval() {
+        let mut fixture = Fixture::new();
+        let writer = Arc::new(RecordingWriter::default());
+        let schedule = TransmitSchedule::Periodic {
+            message: message("ping"),
+            interval_ms: 30,
+            count: 3,
+        };
+        let started = Instant::now();
+        fixture.start("tick", "s", schedule, Arc::clone(&writer));
+
+        let info = fixture.wait_changed("tick");
+        assert_eq!(info.state, TransmitJobState::Completed);
+        assert_eq!(info.sent, 3);
+        let writes = writer.writes.lock().unwrap();
+        assert_eq!(writes.len(), 3);
+        // 第一条立即发送，之后每条不早于起始时间加整数个周期
+        assert!(writes[2].0 - started >= Duration::from_millis(60));
+        assert_eq!(
+            fixture.scheduler.list_jobs()[0].state,
+            TransmitJobState::Completed
+        );
+    }
+
+    #[test]
+    fn sequence_and_one_shot_complete_in_order() {
+        let mut fixture = Fixture::new();
+        let writer = Arc::new(RecordingWriter::default());
+        let step = |payload: &str, delay_ms| SequenceStep {
+            message: message(payload),
+            delay_ms,
+        };
+        let schedule = TransmitSchedule::Sequence {
+            steps: vec![step("a", 0), step("b", 5)],
+            cycles: 2,
+        };
+        fixture.start("seq", "s", schedule, Arc::clone(&writer));
+        assert_eq!(
+            fixture.wait_changed("seq").state,
+            TransmitJobState::Completed
+        );
+        assert_eq!(writer.payloads(), vec![b"a", b"b", b"a", b"b"]);
+
+        // 时间已过的单次发送立即执行
+        let writer = Arc::new(RecordingWriter::default());
+        let schedule = TransmitSchedule::OneShot {
+            message: message("late"),
+            at_unix_millis: 0,
+        };
+        fixture.start("once", "s", schedule, Arc::clone(&writer));
+        assert_eq!(fixture.wait_changed("once").sent, 1);
+        assert_eq!(writer.payloads(), vec![b"late"]);
+    }
+
+    #[test]
+    fn stop_ends_endless_job_and_removes_it() {
+        let mut fixture = Fixture::new();
+        let writer = Arc::new(RecordingWriter::default());
+        let schedule = TransmitSchedule::Periodic {
+            message: message("ping"),
+            interval_ms: 10,
+            count: 0,
+        };
+        fixture.start("loop", "s", schedule, Arc::clone(&writer));
+        let deadline = Instant::now() + TIMEOUT;
+        while writer.payloads().is_empty() {
+            assert!(Instant::now() < deadline, "job never sent");
+            std::thread::sleep(Duration::from_millis(5));
+        }
+
+        fixture.scheduler.stop_job("loop").unwrap();
+        let info = fixture.wait_changed("loop");
+        assert_eq!(info.state, TransmitJobState::Stopped);
+        assert!(fixture.scheduler.list_jobs().is_empty());
+        let sent = writer.payloads().len();
+        std::thread::sleep(Duration::from_millis(30));
+        assert_eq!(writer.payloads().len(), sent);
+        assert!(fixture.scheduler.stop_job("loop").is_err());
+    }
+
+    #[test]
+    fn write_error_fails_job() {
+        let mut fixture = Fixture::new();
+        let writer = Arc::new(RecordingWriter {
+            fail: true,
+            ..RecordingWriter::default()
+        });
+        let schedule = TransmitSchedule::Periodic {
+            message: message("ping"),
+            interval_ms: 10,
+            count: 0,
+        };
+        fixture.start("broken", "s", schedule, writer);
+
+        let info = fixture.wait_changed("broken");
+        assert_eq!(
+            info.state,
+            TransmitJobState::Failed("write failed: device gone".to_string())
+        );
+        assert_eq!(info.sent, 0);
+        assert_eq!(fixture.scheduler.list_jobs()[0].state, info.state);
+    }
+
+    #[test]
+    fn removing_a_source_stops_only_its_jobs() {
+        let mut fixture = Fixture::new();
+        let schedule = || TransmitSchedule::Periodic {
+            message: message("ping"),
+            interval_ms: 10,
+            count: 0,
+        };
+        fixture.start("a", "first", schedule(), Arc::default());
+        fixture.start("b", "second", schedule(), Arc::default());
+
+        fixture.scheduler.stop_source_jobs("first");
+        assert_eq!(fixture.wait_changed("a").state, TransmitJobState::Stopped);
+        let jobs = fixture.scheduler.list_jobs();
+        assert_eq!(jobs.len(), 1);
+        assert_eq!(jobs[0].id, "b");
+        assert_eq!(jobs[0].state, TransmitJobState::Running);
+    }
+}
@@ -1,49 +1,140 @@
 use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
 
+use crate::core::channel::ChannelConfig;
+use crate::core::encoding::EncodingConfig;
+use crate::core::hex_dump::HexDumpConfig;
 use crate::core::traits::DataSource;
-use crate::core::types::{
-    DataPacket, ParserDescriptor, PipelineItem, SourceInfo, SourceKind, SourceStatus,
-};
-use crate::pipeline::stages::{FloatExtractor, LineSplitter, Stage};
-use crate::pipeline::Pipeline;
+use crate::core::types::{DataPacket, ParserDescriptor, SourceInfo, SourceKind, SourceStatus};
+use crate::pipeline::stages::{KeyValueExtractor, LineSplitter, Stage};
+use crate::pipeline::{Pipeline, PipelineSpec};
+use crate::services::dispatcher::{Dispatcher, SharedHub, SharedPipeline};
 use crate::services::registry::ParserRegistry;
+use crate::services::subscription::SubscriptionHub;
+use crate::services::transmitter::SourceTransmitter;
+use crate::sources::build_source;
+
+const DEMO_IDLE_TIMEOUT_MS: u64 = 100;
 
 pub struct StreamManager {
     sources: HashMap<String, Box<dyn DataSource>>,
-    pipelines: HashMap<String, Pipeline>,
+    dispatchers: HashMap<String, Dispatcher>,
+    pipelines: HashMap<String, SharedPipeline>,
     source_pipelines: HashMap<String, String>,
     registry: ParserRegistry,
+    hub: SharedHub,
 }
 
 impl StreamManager {
     pub fn new() -> Self {
         Self {
             sources: HashMap::new(),
+            dispatchers: HashMap::new(),
             pipelines: HashMap::new(),
             source_pipelines: HashMap::new(),
             registry: ParserRegistry::new(),
+            hub: SharedHub::default(),
         }
     }
 
+    // AppHandle 在 setup 阶段才可用，分发线程在此之前收到的数据只跑管线不推送
+    pub fn set_hub(&self, hub: SubscriptionHub) -> Result<(), String> {
+        self.hub
+            .set(hub)
+            .map_err(|_| "subscription hub already set".to_string())
+    }
+
+    pub fn hub(&self) -> SharedHub {
+        Arc::clone(&self.hub)
+    }
+
     pub fn list_sources(&self) -> Vec<SourceInfo> {
         self.sources
-            .values()
-            .map(|source| SourceInfo {
-                id: source.id().to_string(),
-                label: source.label().to_string(),
-                kind: source.kind(),
-                status: source.status(),
-            })
+            .keys()
+            .filter_map(|source_id| self.source_info(source_id))
             .collect()
     }
 
+    pub fn source_info(&self, source_id: &str) -> Option<SourceInfo> {
+        let source = self.sources.get(source_id)?;
+        let dispatcher = self.dispatchers.get(source_id);
+        let stats = dispatcher.map(Dispatcher::stats).unwrap_or_default();
+        Some(SourceInfo {
+            id: source.id().to_string(),
+            label: source.label().to_string(),
+            kind: source.kind(),
+            status: source.status(),
+            backpressure: dispatcher
+                .map(Dispatcher::channel_config)
+                .unwrap_or_default(),
+            encoding: dispatcher.and_then(Dispatcher::encoding),
+            hex_dump: dispatcher.and_then(Dispatcher::hex_dump),
+            queued_packets: stats.queued,
+            dropped_packets: stats.dropped,
+            coalesced_packets: stats.coalesced,
+        })
+    }
+
     pub fn list_parsers(&self) -> Vec<ParserDescriptor> {
         self.registry.list()
     }
 
-    pub fn add_source(&mut self, source: Box<dyn DataSource>) {
+    pub fn add_source(&mut self, mut source: Box<dyn DataSource>) -> Result<(), String> {
         let id = source.id().to_string();
-        self.sources.insert(id, source);
+        let dispatcher = Dispatcher::spawn(&id, Arc::clone(&self.hub))?;
+        source.set_sender(dispatcher.sender());
+        self.sources.insert(id.clone(), source);
+        if let Some(pipeline) = self
+            .source_pipelines
+            .get(&id)
+            .and_then(|pipeline_id| self.pipelines.get(pipeline_id))
+        {
+            dispatcher.set_pipeline(Some(Arc::clone(pipeline)));
+        }
+        self.dispatchers.insert(id, dispatcher);
+        Ok(())
+    }
+
+    pub fn create_source(
+        &mut self,
+        kind: SourceKind,
+        id: &str,
+        label: &str,
+        config: serde_json::Value,
+    ) -> Result<SourceInfo, String> {
+        validate_id("source", id)?;
+        if self.sources.contains_key(id) {
+            return Err(format!("source already exists: {id}"));
+        }
+
+        let mut source = build_source(&kind, id, label)?;
+        // 未提供配置时允许先创建，启动前再 configure
+        if !config.is_null() {
+            source.configure(config)?;
+        }
+
+        self.add_source(source)?;
+        self.source_info(id)
+            .ok_or_else(|| format!("source not found: {id}"))
+    }
+
+    pub fn remove_source(&mut self, source_id: &str) -> Result<(), String> {
+        let mut source = self
+            .sources
+            .remove(source_id)
+            .ok_or_else(|| format!("source not found: {source_id}"))?;
+        let stopped = source.stop();
+        drop(source);
+        self.dispatchers.remove(source_id);
+        self.release_pipeline(source_id);
+        stopped
+    }
+
+    // 解除 source 与管线的绑定，连同管线及其阶段状态一起释放
+    fn release_pipeline(&mut self, source_id: &str) {
+        if let Some(pipeline_id) = self.source_pipelines.remove(source_id) {
+            self.pipelines.remove(&pipeline_id);
+        }
     }
 
     pub fn start_source(&mut self, source_id: &str) -> Result<(), String> {
@@ -62,13 +153,99 @@ impl StreamManager {
         source.stop()
     }
 
+    pub fn configure_source(
+        &mut self,
+        source_id: &str,
+        config: serde_json::Value,
+    ) -> Result<(), String> {
+        let source = self
+            .sources
+            .get_mut(source_id)
+            .ok_or_else(|| format!("source not found: {source_id}"))?;
+        source.configure(config)
+    }
+
+    pub fn set_backpressure(
+        &mut self,
+        source_id: &str,
+        config: ChannelConfig,
+    ) -> Result<(), String> {
+        config.validate()?;
+        let dispatcher = self
+            .dispatchers
+            .get(source_id)
+            .ok_or_else(|| format!("source not found: {source_id}"))?;
+        dispatcher.set_channel_config(config);
+        Ok(())
+    }
+
+    pub fn set_encoding(
+        &mut self,
+        source_id: &str,
+        config: Option<EncodingConfig>,
+    ) -> Result<(), String> {
+        let dispatcher = self
+            .dispatchers
+            .get(source_id)
+            .ok_or_else(|| format!("source not found: {source_id}"))?;
+        dispatcher.set_encoding(config);
+        Ok(())
+    }
+
+    pub fn set_hex_dump(
+        &mut self,
+        source_id: &str,
+        config: Option<HexDumpConfig>,
+    ) -> Result<(), String> {
+        if let Some(config) = &config {
+            config.validate()?;
+        }
+        let dispatcher = self
+            .dispatchers
+            .get(source_id)
+            .ok_or_else(|| format!("source not found: {source_id}"))?;
+        dispatcher.set_hex_dump(config);
+        Ok(())
+    }
+
+    pub fn handle_device_removed(&mut self, device: &str) -> Vec<String> {
+        self.sources
+            .values_mut()
+            .filter_map(|source| {
+                source
+                    .device_removed(device)
+                    .then(|| source.id().to_string())
+            })
+            .collect()
+    }
+
+    pub fn build_pipeline(&self, spec: &PipelineSpec) -> Result<Pipeline, String> {
+        validate_id("pipeline", &spec.id)?;
+        self.registry.build_pipeline(spec)
+    }
+
     pub fn attach_pipeline(&mut self, source_id: &str, pipeline: Pipeline) -> Result<(), String> {
         if !self.sources.contains_key(source_id) {
             return Err(format!("source not found: {source_id}"));
         }
+        // 管线 id 决定 metrics::<id> 事件名，也是管线表的键，不能被两个 source 同时使用
+        let owner = self
+            .source_pipelines
+            .iter()
+            .find(|(owner, id)| id.as_str() == pipeline.id() && owner.as_str() != source_id);
+        if let Some((owner, _)) = owner {
+            return Err(format!(
+                "pipeline id already used by source {owner}: {}",
+                pipeline.id()
+            ));
+        }
 
-        // TODO: Wire the pipeline into the source's output stream.
+        self.release_pipeline(source_id);
         let pipeline_id = pipeline.id().to_string();
+        let pipeline = Arc::new(Mutex::new(pipeline));
+        if let Some(dispatcher) = self.dispatchers.get(source_id) {
+            dispatcher.set_pipeline(Some(Arc::clone(&pipeline)));
+        }
         self.pipelines.insert(pipeline_id.clone(), pipeline);
         self.source_pipelines
             .insert(source_id.to_string(), pipeline_id);
@@ -77,44 +254,158 @@ impl StreamManager {
 
     #[allow(dead_code)]
     pub fn pipeline_status(&self, pipeline_id: &str) -> Option<(String, SourceKind, SourceStatus)> {
-        self.pipelines.get(pipeline_id).map(|pipeline| {
+        self.pipelines.get(pipeline_id).map(|_| {
             (
-                pipeline.id().to_string(),
+                pipeline_id.to_string(),
                 SourceKind::Unknown,
                 SourceStatus::Stopped,
             )
         })
     }
 
-    pub fn ingest_packet(
-        &mut self,
-        source_id: &str,
-        packet: DataPacket,
-    ) -> Result<(String, Vec<PipelineItem>), String> {
-        let pipeline_id = self.ensure_demo_pipeline(source_id)?;
-        let pipeline = self
-            .pipelines
-            .get(&pipeline_id)
-            .ok_or_else(|| format!("pipeline not found: {pipeline_id}"))?;
-        Ok((pipeline_id, pipeline.process(PipelineItem::Packet(packet))))
+    // 注入一个数据包，和 source 自己读到的数据走同一条分发路径
+    pub fn ingest_packet(&mut self, source_id: &str, packet: DataPacket) -> Result<(), String> {
+        self.ensure_demo_pipeline(source_id)?;
+        let dispatcher = self
+            .dispatchers
+            .get(source_id)
+            .ok_or_else(|| format!("source not found: {source_id}"))?;
+        dispatcher.inject(packet)
+    }
+
+    // 取出发送句柄后即可释放锁，写入设备和等待应答都不再占用 StreamManager
+    pub fn transmitter(&self, source_id: &str) -> Result<SourceTransmitter, String> {
+        let source = self
+            .sources
+            .get(source_id)
+            .ok_or_else(|| format!("source not found: {source_id}"))?;
+        let dispatcher = self
+            .dispatchers
+            .get(source_id)
+            .ok_or_else(|| format!("source not found: {source_id}"))?;
+        Ok(dispatcher.transmitter(source_id, source.writer()?))
     }
 
-    fn ensure_demo_pipeline(&mut self, source_id: &str) -> Result<String, String> {
+    fn ensure_demo_pipeline(&mut self, source_id: &str) -> Result<(), String> {
         if !self.sources.contains_key(source_id) {
             return Err(format!("source not found: {source_id}"));
         }
 
-        if let Some(pipeline_id) = self.source_pipelines.get(source_id) {
-            return Ok(pipeline_id.clone());
+        if self.source_pipelines.contains_key(source_id) {
+            return Ok(());
         }
 
-        let pipeline_id = format!("{source_id}_demo");
-        let mut pipeline = Pipeline::new(&pipeline_id);
-        pipeline.push_stage(Box::new(LineSplitter) as Box<dyn Stage + Send + Sync>);
-        pipeline.push_stage(Box::new(FloatExtractor) as Box<dyn Stage + Send + Sync>);
-        self.pipelines.insert(pipeline_id.clone(), pipeline);
-        self.source_pipelines
-            .insert(source_id.to_string(), pipeline_id.clone());
-        Ok(pipeline_id)
+        // mock_rx 发来的文本不带换行，靠空闲超时把整段当作一行
+        let mut splitter = LineSplitter::default();
+        splitter.idle_timeout_ms = DEMO_IDLE_TIMEOUT_MS;
+        let mut pipeline = Pipeline::new(format!("{source_id}_demo"));
+        pipeline.push_stage(Box::new(splitter) as Box<dyn Stage + Send + Sync>);
+        pipeline.push_stage(Box::new(KeyValueExtractor::default()) as Box<dyn Stage + Send + Sync>);
+        self.attach_pipeline(source_id, pipeline)
+    }
+}
+
+// id 会拼进事件名 data_stream::<id> / metrics::<id>，Tauri 事件名只允许字母数字和 - / : _
+fn validate_id(what: &str, id: &str) -> Result<(), String> {
+    if id.is_empty() {
+        return Err(format!("{what} id must not be empty"));
+    }
+    if !id
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err(format!(
+            "invalid {what} id: {id} (only ASCII letters, digits, '-' and '_' are allowed)"
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::channel::BackpressurePolicy;
+
+    #[test]
+    fn reattach_releases_previous_pipeline() {
+        let mut manager = StreamManager::new();
+        manager
+            .create_source(SourceKind::Network, "net", "Net", serde_json::Value::Null)
+            .unwrap();
+        manager
+            .attach_pipeline("net", Pipeline::new("first"))
+            .unwrap();
+        manager
+            .attach_pipeline("net", Pipeline::new("second"))
+            .unwrap();
+        assert_eq!(manager.pipelines.len(), 1);
+        assert!(manager.pipelines.contains_key("second"));
+
+        manager.remove_source("net").unwrap();
+        assert!(manager.pipelines.is_empty());
+    }
+
+    #[test]
+    fn source_info_reports_channel_counters() {
+        let mut manager = StreamManager::new();
+        manager
+            .create_source(SourceKind::Network, "net", "Net", serde_json::Value::Null)
+            .unwrap();
+        manager.attach_pipeline("net", Pipeline::new("p")).unwrap();
+        manager
+            .set_backpressure(
+                "net",
+                ChannelConfig {
+                    capacity: 1,
+                    policy: BackpressurePolicy::Coalesce,
+                },
+            )
+            .unwrap();
+        let packet = |text: &str, tag: &str| {
+            let mut packet = DataPacket::new("net", text.as_bytes().to_vec(), Some(text.into()));
+            packet.tags.push(tag.to_string());
+            packet
+        };
+
+        // 占住管线让分发线程卡在第一个包上，后面的包只能留在队列里
+        let pipeline = Arc::clone(&manager.pipelines["p"]);
+        let guard = pipeline.lock().unwrap();
+        manager.ingest_packet("net", packet("1", "a")).unwrap();
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(2);
+        while manager.source_info("net").unwrap().queued_packets > 0 {
+            assert!(std::time::Instant::now() < deadline);
+            std::thread::sleep(std::time::Duration::from_millis(5));
+        }
+        for (text, tag) in [("2", "a"), ("3", "a"), ("4", "b")] {
+            manager.ingest_packet("net", packet(text, tag)).unwrap();
+        }
+
+        let info = manager.source_info("net").unwrap();
+        assert_eq!(info.queued_packets, 1);
+        assert_eq!(info.coalesced_packets, 1);
+        assert_eq!(info.dropped_packets, 1);
+        drop(guard);
+    }
+
+    #[test]
+    fn pipeline_id_belongs_to_one_source() {
+        let mut manager = StreamManager::new();
+        for id in ["a", "b"] {
+            manager
+                .create_source(SourceKind::Network, id, id, serde_json::Value::Null)
+                .unwrap();
+        }
+        manager.attach_pipeline("a", Pipeline::new("p1")).unwrap();
+        let err = manager
+            .attach_pipeline("b", Pipeline::new("p1"))
+            .unwrap_err();
+        assert!(err.contains("already used by source a"), "{err}");
+
+        // 同一个 source 重新挂同名管线仍然允许
+        manager.attach_pipeline("a", Pipeline::new("p1")).unwrap();
+        manager.attach_pipeline("b", Pipeline::new("p2")).unwrap();
+        manager.remove_source("a").unwrap();
+        assert!(manager.pipelines.contains_key("p2"));
+        assert!(!manager.pipelines.contains_key("p1"));
     }
 }
@@ -1,18 +1,23 @@
 use tauri::{AppHandle, Emitter};
 
-use crate::core::types::{Metric, ParsedEvent};
+use crate::core::hex_dump::HexDump;
+use crate::core::types::{DataPacket, Metric, ParsedEvent, TransmitJobInfo};
 
-#[allow(dead_code)]
 pub struct SubscriptionHub {
     app: AppHandle,
 }
 
-#[allow(dead_code)]
 impl SubscriptionHub {
     pub fn new(app: AppHandle) -> Self {
         Self { app }
     }
 
+    pub fn emit_packet(&self, event: &str, packet: &DataPacket) -> Result<(), String> {
+        self.app
+            .emit(event, packet)
+            .map_err(|err| err.to_string())
+    }
+
     pub fn emit_metric(&self, event: &str, metric: &Metric) -> Result<(), String> {
         self.app
             .emit(event, metric)
@@ -25,5 +30,17 @@ impl SubscriptionHub {
             .map_err(|err| err.to_string())
     }
 
+    pub fn emit_hex_dump(&self, event: &str, dump: &HexDump) -> Result<(), String> {
+        self.app
+            .emit(event, dump)
+            .map_err(|err| err.to_string())
+    }
+
+    pub fn emit_transmit_job(&self, event: &str, job: &TransmitJobInfo) -> Result<(), String> {
+        self.app
+            .emit(event, job)
+            .map_err(|err| err.to_string())
+    }
+
     // TODO: Track active subscriptions and allow per-source filtering.
 }
@@ -0,0 +1,383 @@
+// 请求/应答：发送一段数据后等待第一个匹配的包，并给出往返时延
+use std::sync::{Arc, Condvar, Mutex, Weak};
+use std::time::{Duration, Instant};
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::core::payload::parse_hex;
+use crate::core::types::{DataPacket, Timestamp};
+use crate::services::scheduler::TransmitMessage;
+use crate::services::StreamManager;
+
+pub type FramePredicate = Arc<dyn Fn(&DataPacket) -> bool + Send + Sync>;
+
+// 超过这个时长的等待按上限处理，避免前端传入过大的值让请求一直挂着
+const MAX_TIMEOUT_MS: u64 = 60_000;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ResponsePattern {
+    Prefix {
+        prefix: String,
+    },
+    Regex {
+        pattern: String,
+    },
+    // raw 在 offset 处以 bytes（hex 字符串）开头，可选地要求帧长
+    Frame {
+        #[serde(default)]
+        offset: usize,
+        bytes: String,
+        #[serde(default)]
+        length: Option<usize>,
+    },
+}
+
+impl ResponsePattern {
+    pub fn compile(&self) -> Result<ResponseMatcher, String> {
+        match self {
+            ResponsePattern::Prefix { prefix } => Ok(ResponseMatcher::Prefix(prefix.clone())),
+            ResponsePattern::Regex { pattern } => Regex::new(pattern)
+                .map(ResponseMatcher::Regex)
+                .map_err(|err| format!("invalid response pattern: {err}")),
+            ResponsePattern::Frame {
+                offset,
+                bytes,
+                length,
+            } => {
+                let (offset, length) = (*offset, *length);
+                let bytes = parse_hex(bytes)?;
+                Ok(ResponseMatcher::Predicate(Arc::new(move |packet| {
+                    length.is_none_or(|length| packet.raw.len() == length)
+                        && packet
+                            .raw
+                            .get(offset..)
+                            .is_some_and(|rest| rest.starts_with(&bytes))
+                })))
+            }
+        }
+    }
+}
+
+// Prefix/Regex 在有 text 时按文本匹配，否则按原始字节；Frame 模式编译为按原始字节判断的 Predicate
+#[derive(Clone)]
+pub enum ResponseMatcher {
+    Prefix(String),
+    Regex(Regex),
+    // Rust 侧可直接构造，用于命令层无法表达的判断
+    Predicate(FramePredicate),
+}
+
+impl ResponseMatcher {
+    pub fn matches(&self, packet: &DataPacket) -> bool {
+        match self {
+            ResponseMatcher::Prefix(prefix) => match &packet.text {
+                Some(text) => text.starts_with(prefix.as_str()),
+                None => packet.raw.starts_with(prefix.as_bytes()),
+            },
+            ResponseMatcher::Regex(regex) => match &packet.text {
+                Some(text) => regex.is_match(text),
+                None => regex.is_match(&String::from_utf8_lossy(&packet.raw)),
+            },
+            ResponseMatcher::Predicate(predicate) => predicate(packet),
+        }
+    }
+}
+
+#[derive(Default)]
+struct ResponseSlot {
+    packet: Mutex<Option<DataPacket>>,
+    ready: Condvar,
+}
+
+struct Waiter {
+    matcher: ResponseMatcher,
+    // 早于发送时刻的包（仍在队列里的旧数据）不算应答
+    since: Timestamp,
+    slot: Weak<ResponseSlot>,
+}
+
+// 挂在 Dispatcher 上；等待方超时或放弃后 slot 被释放，对应的 Waiter 随之清除
+#[derive(Clone, Default)]
+pub struct ResponseWaiters {
+    waiters: Arc<Mutex<Vec<Waiter>>>,
+}
+
+impl ResponseWaiters {
+    pub fn expect(
+        &self,
+        source_id: &str,
+        matcher: ResponseMatcher,
+        since: Timestamp,
+    ) -> PendingResponse {
+        let slot = Arc::new(ResponseSlot::default());
+        if let Ok(mut waiters) = self.waiters.lock() {
+            waiters.push(Waiter {
+                matcher,
+                since,
+                slot: Arc::downgrade(&slot),
+            });
+        }
+        PendingResponse {
+            source_id: source_id.to_string(),
+            sent_at: since,
+            slot,
+        }
+    }
+
+    pub fn offer(&self, packet: &DataPacket) {
+        let Ok(mut waiters) = self.waiters.lock() else {
+            return;
+        };
+        waiters.retain(|waiter| {
+            let Some(slot) = waiter.slot.upgrade() else {
+                return false;
+            };
+            if packet.ts < waiter.since || !waiter.matcher.matches(packet) {
+                return true;
+            }
+            if let Ok(mut matched) = slot.packet.lock() {
+                *matched = Some(packet.clone());
+            }
+            slot.ready.notify_all();
+            false
+        });
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransactionRequest {
+    pub source_id: String,
+    #[serde(flatten)]
+    pub message: TransmitMessage,
+    pub expect: ResponsePattern,
+    pub timeout_ms: u64,
+}
+
+impl TransactionRequest {
+    pub fn timeout(&self) -> Result<Duration, String> {
+        if self.timeout_ms == 0 {
+            return Err("timeout_ms must be greater than 0".to_string());
+        }
+        Ok(Duration::from_millis(self.timeout_ms.min(MAX_TIMEOUT_MS)))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransactionResult {
+    pub response: DataPacket,
+    pub sent_at: Timestamp,
+    // 从写入前一刻到应答首字节被读到的时间
+    pub latency_micros: u64,
+}
+
+pub struct PendingResponse {
+    source_id: String,
+    sent_at: Timestamp,
+    slot: Arc<ResponseSlot>,
+}
+
+impl PendingResponse {
+    pub fn wait(self, timeout: Duration) -> Result<TransactionResult, String> {
+        let deadline = Instant::now() + timeout;
+        let mut matched = self
+            .slot
+            .packet
+            .lock()
+            .map_err(|_| "lock poisoned".to_string())?;
+        loop {
+            if let Some(response) = matched.take() {
+                return Ok(TransactionResult {
+                    latency_micros: response
+                        .ts
+                        .mono_micros
+                        .saturating_sub(self.sent_at.mono_micros),
+                    sent_at: self.sent_at,
+                    response,
+                });
+            }
+            let now = Instant::now();
+            if now >= deadline {
+                return Err(format!(
+                    "timed out waiting for response from {} after {} ms",
+                    self.source_id,
+                    timeout.as_millis()
+                ));
+            }
+            matched = self
+                .slot
+                .ready
+                .wait_timeout(matched, deadline - now)
+                .map_err(|_| "lock poisoned".to_string())?
+                .0;
+        }
+    }
+}
+
+// 只在取发送句柄时持有 StreamManager 的锁，写入和等待应答期间其它命令和发送任务不受影响
+pub fn transact(
+    manager: &Mutex<StreamManager>,
+    source_id: &str,
+    message: &TransmitMessage,
+    matcher: ResponseMatcher,
+    timeout: Duration,
+) -> Result<TransactionResult, String> {
+    let transmitter = manager
+        .lock()
+        .map_err(|_| "lock poisoned".to_string())?
+        .transmitter(source_id)?;
+    transmitter.transact(message, matcher, timeout)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::core::channel::{bounded, ChannelConfig, PacketReceiver};
+    use crate::core::traits::SourceWriter;
+    use crate::services::transmitter::SourceTransmitter;
+
+    type Reply = Box<dyn Fn(&[u8]) -> Vec<DataPacket> + Send + Sync>;
+
+    // 模拟设备：每次写入后按顺序交出应答包，和分发线程把收到的包交给等待方一样
+    struct FakeDevice {
+        responses: ResponseWaiters,
+        reply: Reply,
+    }
+
+    impl SourceWriter for FakeDevice {
+        fn write(&self, data: &[u8]) -> Result<(), String> {
+            for packet in (self.reply)(data) {
+                self.responses.offer(&packet);
+            }
+            Ok(())
+        }
+    }
+
+    fn device(reply: Reply) -> (SourceTransmitter, PacketReceiver) {
+        let responses = ResponseWaiters::default();
+        let (sender, receiver) = bounded(ChannelConfig::default());
+        let writer = Arc::new(FakeDevice {
+            responses: responses.clone(),
+            reply,
+        });
+        let transmitter =
+            SourceTransmitter::new("dev", writer, sender, Arc::new(Mutex::new(None)), responses);
+        (transmitter, receiver)
+    }
+
+    fn text(text: &str) -> DataPacket {
+        DataPacket::new("dev", text.as_bytes().to_vec(), Some(text.to_string()))
+    }
+
+    fn raw(bytes: &[u8]) -> DataPacket {
+        DataPacket::new("dev", bytes.to_vec(), None)
+    }
+
+    fn message(payload: &str) -> TransmitMessage {
+        serde_json::from_value(json!({ "payload": payload })).unwrap()
+    }
+
+    fn pattern(value: serde_json::Value) -> ResponseMatcher {
+        serde_json::from_value::<ResponsePattern>(value)
+            .unwrap()
+            .compile()
+            .unwrap()
+    }
+
+    const TIMEOUT: Duration = Duration::from_secs(1);
+
+    #[test]
+    fn prefix_and_regex_match_text_replies() {
+        let (transmitter, receiver) = device(Box::new(|_| vec![text("OK"), text("VER 1.2.3")]));
+
+        let result = transmitter
+            .transact(
+                &message("AT+VER?"),
+                pattern(json!({ "kind": "prefix", "prefix": "VER" })),
+                TIMEOUT,
+            )
+            .unwrap();
+        assert_eq!(result.response.text.as_deref(), Some("VER 1.2.3"));
+        assert!(result.response.ts >= result.sent_at);
+        let echo = receiver.recv_timeout(TIMEOUT).unwrap().unwrap();
+        assert!(echo.is_tx());
+        assert_eq!(echo.raw, b"AT+VER?");
+
+        let result = transmitter
+            .transact(
+                &message("AT+VER?"),
+                pattern(json!({ "kind": "regex", "pattern": r"^VER \d+\.\d+" })),
+                TIMEOUT,
+            )
+            .unwrap();
+        assert_eq!(result.response.text.as_deref(), Some("VER 1.2.3"));
+    }
+
+    #[test]
+    fn frame_pattern_checks_offset_and_length() {
+        let (transmitter, _receiver) = device(Box::new(|_| {
+            vec![
+                raw(&[0x00, 0xAA, 0x55]),
+                raw(&[0xAA, 0x55, 0x10, 0x00]),
+                raw(&[0x01, 0xAA, 0x55, 0x10]),
+            ]
+        }));
+        let matcher =
+            pattern(json!({ "kind": "frame", "offset": 1, "bytes": "AA 55", "length": 4 }));
+        let result = transmitter
+            .transact(&message("poll"), matcher, TIMEOUT)
+            .unwrap();
+        assert_eq!(result.response.raw, [0x01, 0xAA, 0x55, 0x10]);
+    }
+
+    #[test]
+    fn predicate_matcher_from_rust() {
+        let (transmitter, _receiver) = device(Box::new(|data| {
+            vec![
+                raw(data),
+                raw(&data.iter().rev().copied().collect::<Vec<_>>()),
+            ]
+        }));
+        let matcher = ResponseMatcher::Predicate(Arc::new(|packet| packet.raw.starts_with(b"c")));
+        let result = transmitter
+            .transact(&message("abc"), matcher, TIMEOUT)
+            .unwrap();
+        assert_eq!(result.response.raw, b"cba");
+    }
+
+    #[test]
+    fn times_out_without_matching_reply() {
+        let (transmitter, _receiver) = device(Box::new(|_| vec![text("ERROR")]));
+        let err = transmitter
+            .transact(
+                &message("AT"),
+                pattern(json!({ "kind": "prefix", "prefix": "OK" })),
+                Duration::from_millis(20),
+            )
+            .err()
+            .unwrap();
+        assert!(err.contains("timed out"), "{err}");
+    }
+
+    #[test]
+    fn request_timeout_is_validated_and_clamped() {
+        let request = |timeout_ms: u64| -> TransactionRequest {
+            serde_json::from_value(json!({
+                "source_id": "dev",
+                "payload": "AT",
+                "expect": { "kind": "prefix", "prefix": "OK" },
+                "timeout_ms": timeout_ms,
+            }))
+            .unwrap()
+        };
+        assert!(request(0).timeout().is_err());
+        assert_eq!(request(250).timeout(), Ok(Duration::from_millis(250)));
+        assert_eq!(
+            request(u64::MAX).timeout(),
+            Ok(Duration::from_millis(MAX_TIMEOUT_MS))
+        );
+    }
+}
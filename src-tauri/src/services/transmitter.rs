@@ -0,0 +1,77 @@
+// 发送句柄：从 StreamManager 取出后在锁外写入设备，再把发送内容作为 tx 包回显
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::core::channel::PacketSender;
+use crate::core::encoding::EncodingConfig;
+use crate::core::payload::{PayloadEncoding, PayloadFormat};
+use crate::core::traits::SharedWriter;
+use crate::core::types::{DataPacket, Timestamp, TX_TAG};
+use crate::services::scheduler::TransmitMessage;
+use crate::services::transaction::{ResponseMatcher, ResponseWaiters, TransactionResult};
+
+#[derive(Clone)]
+pub struct SourceTransmitter {
+    source_id: String,
+    writer: SharedWriter,
+    sender: PacketSender,
+    // 与分发线程共用，发送时按 source 当前的文本编码转换
+    encoding: Arc<Mutex<Option<EncodingConfig>>>,
+    responses: ResponseWaiters,
+}
+
+impl SourceTransmitter {
+    pub fn new(
+        source_id: &str,
+        writer: SharedWriter,
+        sender: PacketSender,
+        encoding: Arc<Mutex<Option<EncodingConfig>>>,
+        responses: ResponseWaiters,
+    ) -> Self {
+        Self {
+            source_id: source_id.to_string(),
+            writer,
+            sender,
+            encoding,
+            responses,
+        }
+    }
+
+    pub fn send(&self, payload: &str, encoding: PayloadEncoding) -> Result<(), String> {
+        let text_encoding = self
+            .encoding
+            .lock()
+            .ok()
+            .and_then(|config| *config)
+            .map(|config| config.encoding)
+            .unwrap_or_default();
+        let bytes = encoding.encode(payload, text_encoding)?;
+        let text = (encoding.format == PayloadFormat::Text)
+            .then(|| format!("{payload}{}", encoding.line_ending.as_str()));
+        self.transmit(bytes, text)
+    }
+
+    // 先登记等待再发送，避免应答在登记前到达而丢失；阻塞到收到应答或超时
+    pub fn transact(
+        &self,
+        message: &TransmitMessage,
+        matcher: ResponseMatcher,
+        timeout: Duration,
+    ) -> Result<TransactionResult, String> {
+        let pending = self
+            .responses
+            .expect(&self.source_id, matcher, Timestamp::now());
+        self.send(&message.payload, message.encoding)?;
+        pending.wait(timeout)
+    }
+
+    // 写入设备成功后回显，和接收数据按时间顺序出现在同一个流里
+    fn transmit(&self, bytes: Vec<u8>, text: Option<String>) -> Result<(), String> {
+        self.writer.write(&bytes)?;
+        let mut packet = DataPacket::new(&self.source_id, bytes, text);
+        packet.tags.push(TX_TAG.to_string());
+        self.sender
+            .send(packet)
+            .map_err(|_| "dispatcher stopped".to_string())
+    }
+}
@@ -5,3 +5,19 @@ pub mod serial;
 pub use network::NetworkSource;
 pub use rtt::RttSource;
 pub use serial::SerialSource;
+
+use crate::core::traits::DataSource;
+use crate::core::types::SourceKind;
+
+pub fn build_source(
+    kind: &SourceKind,
+    id: &str,
+    label: &str,
+) -> Result<Box<dyn DataSource>, String> {
+    match kind {
+        SourceKind::Serial => Ok(Box::new(SerialSource::new(id, label))),
+        SourceKind::Rtt => Ok(Box::new(RttSource::new(id, label))),
+        SourceKind::Network => Ok(Box::new(NetworkSource::new(id, label))),
+        SourceKind::Unknown => Err("cannot create source of unknown kind".to_string()),
+    }
+}
@@ -1,14 +1,160 @@
-use std::sync::mpsc::Sender;
+use std::collections::HashMap;
+use std::io::{ErrorKind, Read, Write};
+use std::net::{
+    IpAddr, Ipv4Addr, Shutdown, SocketAddr, TcpListener, TcpStream, ToSocketAddrs, UdpSocket,
+};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::Duration;
 
-use crate::core::traits::DataSource;
-use crate::core::types::{DataPacket, SourceKind, SourceStatus};
+use serde::{Deserialize, Serialize};
+
+use crate::core::channel::PacketSender;
+use crate::core::traits::{DataSource, SharedWriter, SourceWriter};
+use crate::core::types::{DataPacket, SourceKind, SourceStatus, Timestamp};
+
+const READ_BUFFER_SIZE: usize = 64 * 1024;
+const POLL_INTERVAL_MS: u64 = 50;
+const CONNECT_TIMEOUT_MS: u64 = 3000;
+const WRITE_TIMEOUT_MS: u64 = 2000;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum NetworkConfig {
+    TcpClient {
+        host: String,
+        port: u16,
+    },
+    TcpServer {
+        #[serde(default = "default_bind")]
+        bind: String,
+        port: u16,
+        #[serde(default)]
+        max_peers: Option<usize>,
+    },
+    Udp {
+        #[serde(default = "default_bind")]
+        bind: String,
+        port: u16,
+        #[serde(default)]
+        multicast_group: Option<IpAddr>,
+        #[serde(default)]
+        multicast_interface: Option<Ipv4Addr>,
+    },
+}
+
+fn default_bind() -> String {
+    "0.0.0.0".to_string()
+}
+
+impl NetworkConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            NetworkConfig::TcpClient { host, port } => {
+                if host.trim().is_empty() {
+                    return Err("host must not be empty".to_string());
+                }
+                if *port == 0 {
+                    return Err("port must be greater than 0".to_string());
+                }
+            }
+            NetworkConfig::TcpServer { max_peers, .. } => {
+                if *max_peers == Some(0) {
+                    return Err("max_peers must be greater than 0".to_string());
+                }
+            }
+            NetworkConfig::Udp {
+                multicast_group, ..
+            } => {
+                if let Some(group) = multicast_group {
+                    if !group.is_multicast() {
+                        return Err(format!("not a multicast address: {group}"));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn resolve(host: &str, port: u16) -> Result<SocketAddr, String> {
+    (host, port)
+        .to_socket_addrs()
+        .map_err(|err| format!("failed to resolve {host}:{port}: {err}"))?
+        .next()
+        .ok_or_else(|| format!("no address for {host}:{port}"))
+}
+
+fn peer_tag(addr: &SocketAddr) -> String {
+    format!("peer:{addr}")
+}
+
+fn is_timeout(err: &std::io::Error) -> bool {
+    matches!(
+        err.kind(),
+        ErrorKind::WouldBlock | ErrorKind::TimedOut | ErrorKind::Interrupted
+    )
+}
+
+// 发送端：TCP 为每个连接保存一份写句柄，UDP 发往配置的组播组，未配置时回复最近一次发来数据的对端
+#[derive(Default)]
+struct Outbound {
+    streams: HashMap<SocketAddr, TcpStream>,
+    udp: Option<UdpSocket>,
+    udp_group: Option<SocketAddr>,
+    udp_peer: Option<SocketAddr>,
+}
+
+impl Outbound {
+    fn add_stream(&mut self, stream: &TcpStream, peer: SocketAddr) {
+        let writer = stream.try_clone().and_then(|writer| {
+            writer.set_write_timeout(Some(Duration::from_millis(WRITE_TIMEOUT_MS)))?;
+            Ok(writer)
+        });
+        if let Ok(writer) = writer {
+            self.streams.insert(peer, writer);
+        }
+    }
+
+    fn write(&mut self, data: &[u8]) -> Result<(), String> {
+        let udp_target = self.udp_group.or(self.udp_peer);
+        if self.streams.is_empty() && udp_target.is_none() {
+            return Err("no connected peer".to_string());
+        }
+        let mut failed = None;
+        // 写超时时对端可能已收到半帧，之后的数据无法再对齐，断开该连接让对端重连
+        self.streams
+            .retain(|peer, stream| match stream.write_all(data) {
+                Ok(()) => true,
+                Err(err) => {
+                    failed.get_or_insert(format!("{peer}: {err}"));
+                    let _ = stream.shutdown(Shutdown::Both);
+                    false
+                }
+            });
+        if let (Some(socket), Some(peer)) = (&self.udp, udp_target) {
+            if let Err(err) = socket.send_to(data, peer) {
+                failed.get_or_insert(format!("{peer}: {err}"));
+            }
+        }
+        match failed {
+            Some(err) => Err(format!("write failed: {err}")),
+            None => Ok(()),
+        }
+    }
+}
 
 pub struct NetworkSource {
     id: String,
     label: String,
-    status: SourceStatus,
-    #[allow(dead_code)]
-    sender: Option<Sender<DataPacket>>,
+    config: Option<NetworkConfig>,
+    status: Arc<Mutex<SourceStatus>>,
+    sender: Option<PacketSender>,
+    running: Arc<AtomicBool>,
+    worker: Option<JoinHandle<()>>,
+    local_addr: Option<SocketAddr>,
+    outbound: Arc<Mutex<Outbound>>,
 }
 
 impl NetworkSource {
@@ -16,8 +162,123 @@ impl NetworkSource {
         Self {
             id: id.into(),
             label: label.into(),
-            status: SourceStatus::Stopped,
+            config: None,
+            status: Arc::new(Mutex::new(SourceStatus::Stopped)),
             sender: None,
+            running: Arc::new(AtomicBool::new(false)),
+            worker: None,
+            local_addr: None,
+            outbound: Arc::new(Mutex::new(Outbound::default())),
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn with_config(mut self, config: NetworkConfig) -> Self {
+        self.config = Some(config);
+        self
+    }
+
+    // 监听端口为 0 时由系统分配，启动后通过这里拿到实际地址
+    #[allow(dead_code)]
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        self.local_addr
+    }
+
+    fn set_status(&self, status: SourceStatus) {
+        if let Ok(mut current) = self.status.lock() {
+            *current = status;
+        }
+    }
+
+    fn is_running(&self) -> bool {
+        self.worker
+            .as_ref()
+            .map(|worker| !worker.is_finished())
+            .unwrap_or(false)
+    }
+
+    fn context(&self) -> WorkerContext {
+        WorkerContext {
+            source_id: self.id.clone(),
+            sender: self.sender.clone(),
+            running: Arc::clone(&self.running),
+            status: Arc::clone(&self.status),
+            outbound: Arc::clone(&self.outbound),
+        }
+    }
+
+    fn open(&mut self, config: &NetworkConfig) -> Result<JoinHandle<()>, String> {
+        let ctx = self.context();
+        let name = format!("net-rx-{}", self.id);
+        let spawn = |f: Box<dyn FnOnce() + Send>| {
+            std::thread::Builder::new()
+                .name(name.clone())
+                .spawn(f)
+                .map_err(|err| err.to_string())
+        };
+
+        match config {
+            NetworkConfig::TcpClient { host, port } => {
+                let addr = resolve(host, *port)?;
+                let stream =
+                    TcpStream::connect_timeout(&addr, Duration::from_millis(CONNECT_TIMEOUT_MS))
+                        .map_err(|err| format!("failed to connect {addr}: {err}"))?;
+                stream
+                    .set_read_timeout(Some(Duration::from_millis(POLL_INTERVAL_MS)))
+                    .map_err(|err| err.to_string())?;
+                self.local_addr = stream.local_addr().ok();
+                ctx.add_stream(&stream, addr);
+                spawn(Box::new(move || ctx.run_tcp_client(stream, addr)))
+            }
+            NetworkConfig::TcpServer {
+                bind,
+                port,
+                max_peers,
+            } => {
+                let addr = resolve(bind, *port)?;
+                let listener = TcpListener::bind(addr)
+                    .map_err(|err| format!("failed to bind {addr}: {err}"))?;
+                listener
+                    .set_nonblocking(true)
+                    .map_err(|err| err.to_string())?;
+                self.local_addr = listener.local_addr().ok();
+                let max_peers = max_peers.unwrap_or(usize::MAX);
+                spawn(Box::new(move || ctx.run_tcp_server(listener, max_peers)))
+            }
+            NetworkConfig::Udp {
+                bind,
+                port,
+                multicast_group,
+                multicast_interface,
+            } => {
+                let addr = resolve(bind, *port)?;
+                let socket =
+                    UdpSocket::bind(addr).map_err(|err| format!("failed to bind {addr}: {err}"))?;
+                match multicast_group {
+                    Some(IpAddr::V4(group)) => socket
+                        .join_multicast_v4(
+                            group,
+                            &multicast_interface.unwrap_or(Ipv4Addr::UNSPECIFIED),
+                        )
+                        .map_err(|err| format!("failed to join {group}: {err}"))?,
+                    Some(IpAddr::V6(group)) => socket
+                        .join_multicast_v6(group, 0)
+                        .map_err(|err| format!("failed to join {group}: {err}"))?,
+                    None => {}
+                }
+                socket
+                    .set_read_timeout(Some(Duration::from_millis(POLL_INTERVAL_MS)))
+                    .map_err(|err| err.to_string())?;
+                self.local_addr = socket.local_addr().ok();
+                let writer = socket.try_clone().map_err(|err| err.to_string())?;
+                if let Ok(mut outbound) = self.outbound.lock() {
+                    outbound.udp = Some(writer);
+                    if let Some(group) = multicast_group {
+                        outbound.udp_group = Some(SocketAddr::new(*group, *port));
+                    }
+                }
+                spawn(Box::new(move || ctx.run_udp(socket)))
+            }
         }
     }
 }
@@ -36,22 +297,396 @@ impl DataSource for NetworkSource {
     }
 
     fn status(&self) -> SourceStatus {
-        self.status.clone()
+        self.status
+            .lock()
+            .map(|status| status.clone())
+            .unwrap_or_else(|_| SourceStatus::Error("lock poisoned".to_string()))
     }
 
     fn start(&mut self) -> Result<(), String> {
-        // TODO: Bind socket, start receive loop, emit DataPacket to sender.
-        self.status = SourceStatus::Running;
-        Ok(())
+        if self.is_running() {
+            return Ok(());
+        }
+        self.stop()?;
+
+        let config = self
+            .config
+            .clone()
+            .ok_or_else(|| "network mode not configured".to_string())?;
+
+        self.running.store(true, Ordering::SeqCst);
+        match self.open(&config) {
+            Ok(worker) => {
+                self.set_status(SourceStatus::Running);
+                self.worker = Some(worker);
+                Ok(())
+            }
+            Err(err) => {
+                self.running.store(false, Ordering::SeqCst);
+                self.set_status(SourceStatus::Error(err.clone()));
+                Err(err)
+            }
+        }
     }
 
     fn stop(&mut self) -> Result<(), String> {
-        // TODO: Close socket and cleanup.
-        self.status = SourceStatus::Stopped;
+        self.running.store(false, Ordering::SeqCst);
+        if let Some(worker) = self.worker.take() {
+            worker
+                .join()
+                .map_err(|_| format!("network worker panicked: {}", self.id))?;
+        }
+        self.local_addr = None;
+        if let Ok(mut outbound) = self.outbound.lock() {
+            *outbound = Outbound::default();
+        }
+        if let Ok(mut status) = self.status.lock() {
+            if matches!(*status, SourceStatus::Running) {
+                *status = SourceStatus::Stopped;
+            }
+        }
         Ok(())
     }
 
-    fn set_sender(&mut self, sender: Sender<DataPacket>) {
+    fn set_sender(&mut self, sender: PacketSender) {
         self.sender = Some(sender);
     }
+
+    fn writer(&self) -> Result<SharedWriter, String> {
+        Ok(Arc::new(NetworkWriter {
+            source_id: self.id.clone(),
+            running: Arc::clone(&self.running),
+            outbound: Arc::clone(&self.outbound),
+        }))
+    }
+
+    fn configure(&mut self, config: serde_json::Value) -> Result<(), String> {
+        let config: NetworkConfig = serde_json::from_value(config)
+            .map_err(|err| format!("invalid network config: {err}"))?;
+        config.validate()?;
+        if self.is_running() {
+            return Err(format!("source is running: {}", self.id));
+        }
+        self.config = Some(config);
+        Ok(())
+    }
+}
+
+impl Drop for NetworkSource {
+    fn drop(&mut self) {
+        let _ = self.stop();
+    }
+}
+
+struct NetworkWriter {
+    source_id: String,
+    running: Arc<AtomicBool>,
+    outbound: Arc<Mutex<Outbound>>,
+}
+
+impl SourceWriter for NetworkWriter {
+    fn write(&self, data: &[u8]) -> Result<(), String> {
+        if !self.running.load(Ordering::SeqCst) {
+            return Err(format!("source is not running: {}", self.source_id));
+        }
+        self.outbound
+            .lock()
+            .map_err(|_| "lock poisoned".to_string())?
+            .write(data)
+    }
+}
+
+#[derive(Clone)]
+struct WorkerContext {
+    source_id: String,
+    sender: Option<PacketSender>,
+    running: Arc<AtomicBool>,
+    status: Arc<Mutex<SourceStatus>>,
+    outbound: Arc<Mutex<Outbound>>,
+}
+
+impl WorkerContext {
+    fn is_running(&self) -> bool {
+        self.running.load(Ordering::SeqCst)
+    }
+
+    fn emit(&self, ts: Timestamp, bytes: &[u8], peer: &SocketAddr) -> bool {
+        let Some(sender) = &self.sender else {
+            return true;
+        };
+        let mut packet = DataPacket::new(&self.source_id, bytes.to_vec(), None).with_timestamp(ts);
+        packet.tags.push(peer_tag(peer));
+        sender.send(packet).is_ok()
+    }
+
+    fn add_stream(&self, stream: &TcpStream, peer: SocketAddr) {
+        if let Ok(mut outbound) = self.outbound.lock() {
+            outbound.add_stream(stream, peer);
+        }
+    }
+
+    fn remove_stream(&self, peer: &SocketAddr) {
+        if let Ok(mut outbound) = self.outbound.lock() {
+            outbound.streams.remove(peer);
+        }
+    }
+
+    fn fail(&self, err: impl Into<String>) {
+        if let Ok(mut status) = self.status.lock() {
+            *status = SourceStatus::Error(err.into());
+        }
+        self.running.store(false, Ordering::SeqCst);
+    }
+
+    fn finish(&self) {
+        self.running.store(false, Ordering::SeqCst);
+        if let Ok(mut status) = self.status.lock() {
+            if matches!(*status, SourceStatus::Running) {
+                *status = SourceStatus::Stopped;
+            }
+        }
+    }
+
+    // Ok 表示 source 被停止，Err 表示连接已结束（对端关闭或出错）
+    fn read_stream(&self, stream: &mut TcpStream, peer: &SocketAddr) -> Result<(), String> {
+        let mut buf = vec![0u8; READ_BUFFER_SIZE];
+        while self.is_running() {
+            match stream.read(&mut buf) {
+                Ok(0) => return Err(format!("connection closed by {peer}")),
+                Ok(n) => {
+                    if !self.emit(Timestamp::now(), &buf[..n], peer) {
+                        self.running.store(false, Ordering::SeqCst);
+                    }
+                }
+                Err(err) if is_timeout(&err) => continue,
+                Err(err) => return Err(format!("{peer}: {err}")),
+            }
+        }
+        Ok(())
+    }
+
+    fn run_tcp_client(self, mut stream: TcpStream, peer: SocketAddr) {
+        match self.read_stream(&mut stream, &peer) {
+            Ok(()) => self.finish(),
+            Err(err) => self.fail(err),
+        }
+    }
+
+    fn run_tcp_server(self, listener: TcpListener, max_peers: usize) {
+        let mut peers: Vec<JoinHandle<()>> = Vec::new();
+        while self.is_running() {
+            peers.retain(|peer| !peer.is_finished());
+            match listener.accept() {
+                Ok((mut stream, addr)) => {
+                    if peers.len() >= max_peers {
+                        // 超出连接上限直接断开
+                        continue;
+                    }
+                    if stream
+                        .set_nonblocking(false)
+                        .and_then(|_| {
+                            stream.set_read_timeout(Some(Duration::from_millis(POLL_INTERVAL_MS)))
+                        })
+                        .is_err()
+                    {
+                        continue;
+                    }
+                    let ctx = self.clone();
+                    ctx.add_stream(&stream, addr);
+                    let handle = std::thread::Builder::new()
+                        .name(format!("net-peer-{}-{addr}", self.source_id))
+                        .spawn(move || {
+                            // 单个 peer 断开不影响 source 整体状态
+                            let _ = ctx.read_stream(&mut stream, &addr);
+                            ctx.remove_stream(&addr);
+                        });
+                    if let Ok(handle) = handle {
+                        peers.push(handle);
+                    }
+                }
+                Err(err) if is_timeout(&err) => {
+                    std::thread::sleep(Duration::from_millis(POLL_INTERVAL_MS));
+                }
+                Err(err) => {
+                    self.fail(format!("accept failed: {err}"));
+                    break;
+                }
+            }
+        }
+        self.running.store(false, Ordering::SeqCst);
+        for peer in peers {
+            let _ = peer.join();
+        }
+        self.finish();
+    }
+
+    fn run_udp(self, socket: UdpSocket) {
+        let mut buf = vec![0u8; READ_BUFFER_SIZE];
+        while self.is_running() {
+            match socket.recv_from(&mut buf) {
+                Ok((n, peer)) => {
+                    if let Ok(mut outbound) = self.outbound.lock() {
+                        outbound.udp_peer = Some(peer);
+                    }
+                    if !self.emit(Timestamp::now(), &buf[..n], &peer) {
+                        break;
+                    }
+                }
+                Err(err) if is_timeout(&err) => continue,
+                Err(err) => {
+                    self.fail(format!("recv failed: {err}"));
+                    return;
+                }
+            }
+        }
+        self.finish();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Read, Write};
+    use std::net::{TcpListener, TcpStream, UdpSocket};
+    use std::time::Duration;
+
+    use super::*;
+    use crate::core::channel::{bounded, ChannelConfig, PacketReceiver};
+
+    const TIMEOUT: Duration = Duration::from_secs(2);
+
+    fn start(config: NetworkConfig) -> (NetworkSource, PacketReceiver) {
+        let (sender, receiver) = bounded(ChannelConfig::default());
+        let mut source = NetworkSource::new("net", "Net").with_config(config);
+        source.set_sender(sender);
+        source.start().expect("start network source");
+        (source, receiver)
+    }
+
+    fn recv(receiver: &PacketReceiver) -> DataPacket {
+        receiver
+            .recv_timeout(TIMEOUT)
+            .unwrap()
+            .expect("packet within timeout")
+    }
+
+    // 对端 accept/connect 与读线程登记写句柄是异步的，等到能写为止
+    fn write_eventually(source: &NetworkSource, data: &[u8]) {
+        let writer = source.writer().unwrap();
+        let deadline = std::time::Instant::now() + TIMEOUT;
+        while let Err(err) = writer.write(data) {
+            assert!(std::time::Instant::now() < deadline, "write failed: {err}");
+            std::thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    #[test]
+    fn tcp_client_round_trip() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let (mut source, receiver) = start(NetworkConfig::TcpClient {
+            host: "127.0.0.1".to_string(),
+            port,
+        });
+        let (mut peer, _) = listener.accept().unwrap();
+        peer.set_read_timeout(Some(TIMEOUT)).unwrap();
+
+        peer.write_all(b"hello").unwrap();
+        let packet = recv(&receiver);
+        assert_eq!(packet.raw, b"hello");
+        assert_eq!(packet.tags, vec![format!("peer:127.0.0.1:{port}")]);
+
+        source.writer().unwrap().write(b"world").unwrap();
+        let mut reply = [0u8; 5];
+        peer.read_exact(&mut reply).unwrap();
+        assert_eq!(&reply, b"world");
+        source.stop().unwrap();
+    }
+
+    #[test]
+    fn tcp_server_tags_and_writes_to_peers() {
+        let (mut source, receiver) = start(NetworkConfig::TcpServer {
+            bind: "127.0.0.1".to_string(),
+            port: 0,
+            max_peers: None,
+        });
+        let addr = source.local_addr().expect("bound address");
+        assert!(source.writer().unwrap().write(b"x").is_err());
+
+        let mut first = TcpStream::connect(addr).unwrap();
+        let mut second = TcpStream::connect(addr).unwrap();
+        first.write_all(b"a").unwrap();
+        second.write_all(b"b").unwrap();
+        let mut tags: Vec<String> = (0..2).flat_map(|_| recv(&receiver).tags).collect();
+        tags.sort();
+        let mut expected = vec![
+            peer_tag(&first.local_addr().unwrap()),
+            peer_tag(&second.local_addr().unwrap()),
+        ];
+        expected.sort();
+        assert_eq!(tags, expected);
+
+        write_eventually(&source, b"all");
+        for peer in [&mut first, &mut second] {
+            peer.set_read_timeout(Some(TIMEOUT)).unwrap();
+            let mut reply = [0u8; 3];
+            peer.read_exact(&mut reply).unwrap();
+            assert_eq!(&reply, b"all");
+        }
+        source.stop().unwrap();
+    }
+
+    #[test]
+    fn udp_replies_to_latest_sender() {
+        let (mut source, receiver) = start(NetworkConfig::Udp {
+            bind: "127.0.0.1".to_string(),
+            port: 0,
+            multicast_group: None,
+            multicast_interface: None,
+        });
+        let addr = source.local_addr().expect("bound address");
+        assert!(source.writer().unwrap().write(b"x").is_err());
+
+        let old = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let latest = UdpSocket::bind("127.0.0.1:0").unwrap();
+        old.send_to(b"one", addr).unwrap();
+        assert_eq!(recv(&receiver).raw, b"one");
+        latest.send_to(b"two", addr).unwrap();
+        let packet = recv(&receiver);
+        assert_eq!(packet.tags, vec![peer_tag(&latest.local_addr().unwrap())]);
+
+        source.writer().unwrap().write(b"reply").unwrap();
+        latest.set_read_timeout(Some(TIMEOUT)).unwrap();
+        let mut buf = [0u8; 16];
+        let (n, from) = latest.recv_from(&mut buf).unwrap();
+        assert_eq!(&buf[..n], b"reply");
+        assert_eq!(from, addr);
+
+        old.set_read_timeout(Some(Duration::from_millis(100)))
+            .unwrap();
+        assert!(old.recv_from(&mut buf).is_err());
+        source.stop().unwrap();
+    }
+
+    #[test]
+    fn tcp_peer_is_dropped_after_failed_write() {
+        let (mut source, _receiver) = start(NetworkConfig::TcpServer {
+            bind: "127.0.0.1".to_string(),
+            port: 0,
+            max_peers: None,
+        });
+        let mut peer = TcpStream::connect(source.local_addr().unwrap()).unwrap();
+        write_eventually(&source, b"ok");
+
+        // 对端不读，发送缓冲写满后超时，只写出了一部分
+        let writer = source.writer().unwrap();
+        assert!(writer.write(&vec![0u8; 64 * 1024 * 1024]).is_err());
+        assert!(writer.write(b"next").is_err());
+
+        peer.set_read_timeout(Some(TIMEOUT)).unwrap();
+        let mut rest = Vec::new();
+        peer.read_to_end(&mut rest).unwrap();
+        assert!(rest.starts_with(b"ok"));
+        assert!(!rest.ends_with(b"next"));
+        source.stop().unwrap();
+    }
 }
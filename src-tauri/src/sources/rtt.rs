@@ -1,14 +1,35 @@
-use std::sync::mpsc::Sender;
 
+use serde::{Deserialize, Serialize};
+
+use crate::core::channel::PacketSender;
 use crate::core::traits::DataSource;
-use crate::core::types::{DataPacket, SourceKind, SourceStatus};
+use crate::core::types::{SourceKind, SourceStatus};
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RttConfig {
+    pub chip: String,
+    pub probe: Option<String>,
+    pub up_channel: usize,
+}
+
+impl RttConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.chip.trim().is_empty() {
+            return Err("chip must not be empty".to_string());
+        }
+        Ok(())
+    }
+}
 
 pub struct RttSource {
     id: String,
     label: String,
+    #[allow(dead_code)]
+    config: RttConfig,
     status: SourceStatus,
     #[allow(dead_code)]
-    sender: Option<Sender<DataPacket>>,
+    sender: Option<PacketSender>,
 }
 
 impl RttSource {
@@ -16,6 +37,7 @@ impl RttSource {
         Self {
             id: id.into(),
             label: label.into(),
+            config: RttConfig::default(),
             status: SourceStatus::Stopped,
             sender: None,
         }
@@ -51,7 +73,18 @@ impl DataSource for RttSource {
         Ok(())
     }
 
-    fn set_sender(&mut self, sender: Sender<DataPacket>) {
+    fn set_sender(&mut self, sender: PacketSender) {
         self.sender = Some(sender);
     }
+
+    fn configure(&mut self, config: serde_json::Value) -> Result<(), String> {
+        let config: RttConfig =
+            serde_json::from_value(config).map_err(|err| format!("invalid rtt config: {err}"))?;
+        config.validate()?;
+        if matches!(self.status, SourceStatus::Running) {
+            return Err(format!("source is running: {}", self.id));
+        }
+        self.config = config;
+        Ok(())
+    }
 }
@@ -1,14 +1,161 @@
-use std::sync::mpsc::Sender;
+use std::io::{ErrorKind, Read};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
 
-use crate::core::traits::DataSource;
-use crate::core::types::{DataPacket, SourceKind, SourceStatus};
+use serde::{Deserialize, Serialize};
+
+use crate::core::channel::PacketSender;
+use crate::core::traits::{DataSource, SharedWriter, SourceWriter};
+use crate::core::types::{DataPacket, SerialPortInfo, SourceKind, SourceStatus, Timestamp};
+
+const READ_BUFFER_SIZE: usize = 4096;
+const READ_TIMEOUT_MS: u64 = 50;
+// 端口超时与读共用，写超时后在此期限内重试
+const WRITE_TIMEOUT_MS: u64 = 2000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DataBits {
+    Five,
+    Six,
+    Seven,
+    Eight,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Parity {
+    None,
+    Odd,
+    Even,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StopBits {
+    One,
+    Two,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FlowControl {
+    None,
+    Software,
+    Hardware,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SerialConfig {
+    pub port: String,
+    pub baud_rate: u32,
+    pub data_bits: DataBits,
+    pub parity: Parity,
+    pub stop_bits: StopBits,
+    pub flow_control: FlowControl,
+}
+
+impl Default for SerialConfig {
+    fn default() -> Self {
+        Self {
+            port: String::new(),
+            baud_rate: 115_200,
+            data_bits: DataBits::Eight,
+            parity: Parity::None,
+            stop_bits: StopBits::One,
+            flow_control: FlowControl::None,
+        }
+    }
+}
+
+impl SerialConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.port.trim().is_empty() {
+            return Err("serial port not configured".to_string());
+        }
+        if self.baud_rate == 0 {
+            return Err("baud_rate must be greater than 0".to_string());
+        }
+        Ok(())
+    }
+
+    fn open(&self) -> Result<Box<dyn serialport::SerialPort>, String> {
+        self.validate()?;
+        serialport::new(&self.port, self.baud_rate)
+            .data_bits(match self.data_bits {
+                DataBits::Five => serialport::DataBits::Five,
+                DataBits::Six => serialport::DataBits::Six,
+                DataBits::Seven => serialport::DataBits::Seven,
+                DataBits::Eight => serialport::DataBits::Eight,
+            })
+            .parity(match self.parity {
+                Parity::None => serialport::Parity::None,
+                Parity::Odd => serialport::Parity::Odd,
+                Parity::Even => serialport::Parity::Even,
+            })
+            .stop_bits(match self.stop_bits {
+                StopBits::One => serialport::StopBits::One,
+                StopBits::Two => serialport::StopBits::Two,
+            })
+            .flow_control(match self.flow_control {
+                FlowControl::None => serialport::FlowControl::None,
+                FlowControl::Software => serialport::FlowControl::Software,
+                FlowControl::Hardware => serialport::FlowControl::Hardware,
+            })
+            .timeout(Duration::from_millis(READ_TIMEOUT_MS))
+            .open()
+            .map_err(|err| format!("failed to open {}: {err}", self.port))
+    }
+}
+
+pub fn available_ports() -> Result<Vec<SerialPortInfo>, String> {
+    let mut ports: Vec<SerialPortInfo> = serialport::available_ports()
+        .map_err(|err| err.to_string())?
+        .into_iter()
+        .map(|port| {
+            let mut info = SerialPortInfo {
+                name: port.port_name,
+                port_type: "unknown".to_string(),
+                vid: None,
+                pid: None,
+                serial_number: None,
+                manufacturer: None,
+                product: None,
+            };
+            match port.port_type {
+                serialport::SerialPortType::UsbPort(usb) => {
+                    info.port_type = "usb".to_string();
+                    info.vid = Some(usb.vid);
+                    info.pid = Some(usb.pid);
+                    info.serial_number = usb.serial_number;
+                    info.manufacturer = usb.manufacturer;
+                    info.product = usb.product;
+                }
+                serialport::SerialPortType::PciPort => info.port_type = "pci".to_string(),
+                serialport::SerialPortType::BluetoothPort => {
+                    info.port_type = "bluetooth".to_string()
+                }
+                serialport::SerialPortType::Unknown => {}
+            }
+            info
+        })
+        .collect();
+    ports.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(ports)
+}
 
 pub struct SerialSource {
     id: String,
     label: String,
-    status: SourceStatus,
-    #[allow(dead_code)]
-    sender: Option<Sender<DataPacket>>,
+    config: SerialConfig,
+    status: Arc<Mutex<SourceStatus>>,
+    sender: Option<PacketSender>,
+    running: Arc<AtomicBool>,
+    reader: Option<JoinHandle<()>>,
+    writer: Arc<Mutex<Option<Box<dyn serialport::SerialPort>>>>,
 }
 
 impl SerialSource {
@@ -16,10 +163,33 @@ impl SerialSource {
         Self {
             id: id.into(),
             label: label.into(),
-            status: SourceStatus::Stopped,
+            config: SerialConfig::default(),
+            status: Arc::new(Mutex::new(SourceStatus::Stopped)),
             sender: None,
+            running: Arc::new(AtomicBool::new(false)),
+            reader: None,
+            writer: Arc::new(Mutex::new(None)),
         }
     }
+
+    #[allow(dead_code)]
+    pub fn with_config(mut self, config: SerialConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    fn set_status(&self, status: SourceStatus) {
+        if let Ok(mut current) = self.status.lock() {
+            *current = status;
+        }
+    }
+
+    fn is_running(&self) -> bool {
+        self.reader
+            .as_ref()
+            .map(|reader| !reader.is_finished())
+            .unwrap_or(false)
+    }
 }
 
 impl DataSource for SerialSource {
@@ -36,22 +206,241 @@ impl DataSource for SerialSource {
     }
 
     fn status(&self) -> SourceStatus {
-        self.status.clone()
+        self.status
+            .lock()
+            .map(|status| status.clone())
+            .unwrap_or_else(|_| SourceStatus::Error("lock poisoned".to_string()))
     }
 
     fn start(&mut self) -> Result<(), String> {
-        // TODO: Open serial port, start read loop, emit DataPacket to sender.
-        self.status = SourceStatus::Running;
+        if self.is_running() {
+            return Ok(());
+        }
+        // 上一次读线程可能因设备断开而退出，先回收
+        self.stop()?;
+
+        let port = match self.config.open() {
+            Ok(port) => port,
+            Err(err) => {
+                self.set_status(SourceStatus::Error(err.clone()));
+                return Err(err);
+            }
+        };
+
+        let writer = match port.try_clone() {
+            Ok(writer) => writer,
+            Err(err) => {
+                let err = format!("failed to open {}: {err}", self.config.port);
+                self.set_status(SourceStatus::Error(err.clone()));
+                return Err(err);
+            }
+        };
+
+        self.running.store(true, Ordering::SeqCst);
+        self.set_status(SourceStatus::Running);
+
+        let reader = ReadLoop {
+            source_id: self.id.clone(),
+            port,
+            sender: self.sender.clone(),
+            running: Arc::clone(&self.running),
+            status: Arc::clone(&self.status),
+        };
+        let handle = std::thread::Builder::new()
+            .name(format!("serial-rx-{}", self.id))
+            .spawn(move || reader.run())
+            .map_err(|err| err.to_string())?;
+        self.reader = Some(handle);
+        if let Ok(mut slot) = self.writer.lock() {
+            *slot = Some(writer);
+        }
         Ok(())
     }
 
     fn stop(&mut self) -> Result<(), String> {
-        // TODO: Close serial port and cleanup.
-        self.status = SourceStatus::Stopped;
+        self.running.store(false, Ordering::SeqCst);
+        if let Some(reader) = self.reader.take() {
+            reader
+                .join()
+                .map_err(|_| format!("serial reader panicked: {}", self.id))?;
+        }
+        if let Ok(mut slot) = self.writer.lock() {
+            *slot = None;
+        }
+        // 读线程异常退出时保留错误状态，便于前端展示
+        if let Ok(mut status) = self.status.lock() {
+            if matches!(*status, SourceStatus::Running) {
+                *status = SourceStatus::Stopped;
+            }
+        }
         Ok(())
     }
 
-    fn set_sender(&mut self, sender: Sender<DataPacket>) {
+    fn set_sender(&mut self, sender: PacketSender) {
         self.sender = Some(sender);
     }
+
+    fn device_removed(&mut self, device: &str) -> bool {
+        if self.config.port != device {
+            return false;
+        }
+        let _ = self.stop();
+        self.set_status(SourceStatus::Error("disconnected".to_string()));
+        true
+    }
+
+    fn writer(&self) -> Result<SharedWriter, String> {
+        Ok(Arc::new(SerialWriter {
+            source_id: self.id.clone(),
+            running: Arc::clone(&self.running),
+            port: Arc::clone(&self.writer),
+        }))
+    }
+
+    fn configure(&mut self, config: serde_json::Value) -> Result<(), String> {
+        let config: SerialConfig = serde_json::from_value(config)
+            .map_err(|err| format!("invalid serial config: {err}"))?;
+        config.validate()?;
+        if self.is_running() {
+            return Err(format!("source is running: {}", self.id));
+        }
+        self.config = config;
+        Ok(())
+    }
+}
+
+impl Drop for SerialSource {
+    fn drop(&mut self) {
+        let _ = self.stop();
+    }
+}
+
+// 与 source 共用端口槽位：停止时清空，重新启动后换成新的端口
+struct SerialWriter {
+    source_id: String,
+    running: Arc<AtomicBool>,
+    port: Arc<Mutex<Option<Box<dyn serialport::SerialPort>>>>,
+}
+
+impl SourceWriter for SerialWriter {
+    fn write(&self, data: &[u8]) -> Result<(), String> {
+        if !self.running.load(Ordering::SeqCst) {
+            return Err(format!("source is not running: {}", self.source_id));
+        }
+        let mut port = self.port.lock().map_err(|_| "lock poisoned".to_string())?;
+        let port = port
+            .as_mut()
+            .ok_or_else(|| format!("source is not running: {}", self.source_id))?;
+        write_all(port.as_mut(), data)
+    }
+}
+
+fn write_all(port: &mut dyn serialport::SerialPort, mut data: &[u8]) -> Result<(), String> {
+    let deadline = Instant::now() + Duration::from_millis(WRITE_TIMEOUT_MS);
+    while !data.is_empty() {
+        match port.write(data) {
+            Ok(0) => return Err("write failed: port accepted no data".to_string()),
+            Ok(n) => data = &data[n..],
+            Err(err)
+                if matches!(err.kind(), ErrorKind::TimedOut | ErrorKind::Interrupted)
+                    && Instant::now() < deadline =>
+            {
+                continue;
+            }
+            Err(err) => return Err(format!("write failed: {err}")),
+        }
+    }
+    port.flush().map_err(|err| format!("write failed: {err}"))
+}
+
+struct ReadLoop {
+    source_id: String,
+    port: Box<dyn serialport::SerialPort>,
+    sender: Option<PacketSender>,
+    running: Arc<AtomicBool>,
+    status: Arc<Mutex<SourceStatus>>,
+}
+
+impl ReadLoop {
+    fn run(mut self) {
+        let mut buf = [0u8; READ_BUFFER_SIZE];
+        while self.running.load(Ordering::SeqCst) {
+            match self.port.read(&mut buf) {
+                Ok(0) => continue,
+                Ok(n) => {
+                    let ts = Timestamp::now();
+                    let Some(sender) = &self.sender else {
+                        continue;
+                    };
+                    let packet = DataPacket::new(&self.source_id, buf[..n].to_vec(), None)
+                        .with_timestamp(ts);
+                    if sender.send(packet).is_err() {
+                        // 接收端已关闭，没有继续读取的意义
+                        break;
+                    }
+                }
+                Err(err) if matches!(err.kind(), ErrorKind::TimedOut | ErrorKind::Interrupted) => {
+                    continue;
+                }
+                Err(err) => {
+                    if let Ok(mut status) = self.status.lock() {
+                        *status = SourceStatus::Error(err.to_string());
+                    }
+                    break;
+                }
+            }
+        }
+        self.running.store(false, Ordering::SeqCst);
+        if let Ok(mut status) = self.status.lock() {
+            if matches!(*status, SourceStatus::Running) {
+                *status = SourceStatus::Stopped;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Read, Write};
+    use std::time::Duration;
+
+    use serialport::{SerialPort, TTYPort};
+
+    use super::*;
+    use crate::core::channel::{bounded, ChannelConfig};
+
+    #[test]
+    fn pty_round_trip() {
+        let (mut master, slave) = TTYPort::pair().expect("pty pair");
+        let path = slave.name().expect("pty path");
+        let (sender, receiver) = bounded(ChannelConfig::default());
+        let mut source = SerialSource::new("pty", "PTY").with_config(SerialConfig {
+            port: path,
+            ..SerialConfig::default()
+        });
+        source.set_sender(sender);
+        source.start().expect("open pty");
+
+        master.write_all(b"ping").unwrap();
+        let mut received = Vec::new();
+        while received.len() < 4 {
+            let packet = receiver
+                .recv_timeout(Duration::from_secs(2))
+                .unwrap()
+                .expect("packet from pty");
+            assert_eq!(packet.source_id, "pty");
+            received.extend(packet.raw);
+        }
+        assert_eq!(received, b"ping");
+
+        let writer = source.writer().unwrap();
+        writer.write(b"pong").unwrap();
+        master.set_timeout(Duration::from_secs(2)).unwrap();
+        let mut reply = [0u8; 4];
+        master.read_exact(&mut reply).unwrap();
+        assert_eq!(&reply, b"pong");
+
+        source.stop().unwrap();
+        assert!(writer.write(b"x").is_err());
+    }
 }